@@ -0,0 +1,141 @@
+//! Encryption of the AIC image payload.
+//!
+//! An encrypted image has DATA1 and DATA2 encrypted as one AES-128-CBC
+//! stream, followed by the IV:
+//!
+//! ```text
+//! [pbp_offset..]   = DATA1 + DATA2, encrypted
+//! [iv_offset..]    = IV (16 bytes)
+//! ```
+//!
+//! Both sections are padded to 256 bytes, so no cipher padding is needed.
+//! The AES key is not stored in the image; it is programmed into the SSK
+//! eFuse and used by the BROM to decrypt the payload after the signature
+//! over the encrypted image has been checked.
+
+use aes::Aes128;
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::NoPadding};
+use std::path::Path;
+
+use crate::error::{self, Error, Result};
+
+/// HEAD2 enc algo: payload is not encrypted.
+pub const ENC_ALGO_NONE: u32 = 0;
+/// HEAD2 enc algo: payload is AES-128-CBC encrypted.
+pub const ENC_ALGO_AES128_CBC: u32 = 1;
+
+/// AES block size, which is also the IV length.
+pub const AES_BLOCK_LEN: usize = 16;
+
+/// AES-128 key and CBC initialization vector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AesKey {
+    pub key: [u8; 16],
+    pub iv: [u8; AES_BLOCK_LEN],
+}
+
+impl AesKey {
+    /// Parse a key file: 32 raw bytes, or 64 hex digits, key first then IV.
+    ///
+    /// Whitespace in a hex key file is ignored.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let raw = if data.len() == 32 {
+            data.to_vec()
+        } else {
+            let text = std::str::from_utf8(data)
+                .map_err(|_| Error::Key("Key file is neither binary nor hex".into()))?;
+            from_hex(&text.split_whitespace().collect::<String>())?
+        };
+        if raw.len() != 32 {
+            return Err(Error::Key(format!(
+                "Key file must hold a 16-byte key and a 16-byte IV, found {} bytes",
+                raw.len()
+            )));
+        }
+        let (key, iv) = raw.split_at(16);
+        Ok(Self {
+            key: key.try_into().unwrap(),
+            iv: iv.try_into().unwrap(),
+        })
+    }
+
+    /// Load a key file, see [`AesKey::parse`].
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&error::read(path)?)
+            .map_err(|e| Error::Key(format!("Invalid AES key file {path:?}: {e}")))
+    }
+}
+
+/// Encrypt `data` in place, its length must be a multiple of the block size.
+pub fn encrypt(key: &AesKey, data: &mut [u8]) -> Result<()> {
+    let len = data.len();
+    cbc::Encryptor::<Aes128>::new(&key.key.into(), &key.iv.into())
+        .encrypt_padded_mut::<NoPadding>(data, len)
+        .map_err(|_| {
+            Error::Crypto("Encrypted data is not a multiple of the AES block size".into())
+        })?;
+    Ok(())
+}
+
+/// Decrypt `data` in place, its length must be a multiple of the block size.
+pub fn decrypt(key: &AesKey, data: &mut [u8]) -> Result<()> {
+    cbc::Decryptor::<Aes128>::new(&key.key.into(), &key.iv.into())
+        .decrypt_padded_mut::<NoPadding>(data)
+        .map_err(|_| {
+            Error::Crypto("Encrypted data is not a multiple of the AES block size".into())
+        })?;
+    Ok(())
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return Err(Error::Key("Odd number of hex digits".into()));
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            s.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(|| Error::Key(format!("Invalid hex digits at offset {i}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_round_trip() {
+        // NIST SP 800-38A F.2.1 CBC-AES128.Encrypt, first block
+        let key =
+            AesKey::parse(b"2b7e151628aed2a6abf7158809cf4f3c\n000102030405060708090a0b0c0d0e0f\n")
+                .unwrap();
+        let plain: Vec<u8> = (0..64u8).collect();
+        let mut data = *b"\x6b\xc1\xbe\xe2\x2e\x40\x9f\x96\xe9\x3d\x7e\x11\x73\x93\x17\x2a";
+        encrypt(&key, &mut data).unwrap();
+        assert_eq!(
+            data,
+            *b"\x76\x49\xab\xac\x81\x19\xb2\x46\xce\xe9\x8e\x9b\x12\xe9\x19\x7d"
+        );
+
+        let mut data = plain.clone();
+        encrypt(&key, &mut data).unwrap();
+        assert_ne!(data, plain);
+        decrypt(&key, &mut data).unwrap();
+        assert_eq!(data, plain);
+
+        assert!(encrypt(&key, &mut [0; 15]).is_err());
+    }
+
+    #[test]
+    fn parse_key_file() {
+        let raw: Vec<u8> = (0..32).collect();
+        let key = AesKey::parse(&raw).unwrap();
+        assert_eq!(key.key[15], 15);
+        assert_eq!(key.iv[0], 16);
+        assert!(AesKey::parse(&raw[..31]).is_err());
+        assert!(AesKey::parse(b"00112233").is_err());
+        assert!(AesKey::parse(&[b'z'; 64]).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! SD card and eMMC boot media images.
+//!
+//! Two layouts are supported:
+//!
+//! - Raw (`Sdmc0..2` boot devices): the AIC image is written at fixed
+//!   sectors of the card. The BROM probes sector 34, right behind the
+//!   protective MBR and primary GPT, so a GPT can share the card.
+//! - FAT32 (`Sdfat32` and `Udisk` boot devices): an MBR disk with one FAT32
+//!   partition. The BROM reads `bootcfg.txt` from its root directory and
+//!   loads the file named by the `boot0` entry.
+
+use fatfs::{FatType, FileSystem, FormatVolumeOptions, FsOptions};
+use std::io::{Cursor, Write};
+
+use crate::error::{Error, Result};
+use crate::verify;
+
+/// Sector size of SD cards and eMMC.
+pub const SECTOR_SIZE: usize = 512;
+/// Sector the BROM loads the boot image from on raw SD/eMMC.
+pub const SDMC_BOOT_SECTOR: u64 = 34;
+/// Name of the boot config file on a FAT32 boot medium.
+pub const BOOT_CFG_NAME: &str = "bootcfg.txt";
+/// Default name of the boot image file on a FAT32 boot medium.
+pub const DEFAULT_BOOT_FILE_NAME: &str = "bootloader.aic";
+/// First sector of the FAT32 partition, 1 MiB aligned like card formatters do.
+pub const FAT32_PARTITION_SECTOR: u64 = 2048;
+/// Number of sectors addressable by the 32-bit SD/eMMC block address.
+pub const SDMC_SECTOR_COUNT: u64 = 1 << 32;
+
+/// Build a raw SD/eMMC image with a copy of `boot` at each of `sectors`.
+///
+/// The image ends with the last copy, sectors in between are zero.
+pub fn sdmc_image(boot: &[u8], sectors: &[u64]) -> Result<Vec<u8>> {
+    check_boot_image(boot)?;
+    let boot_sectors = boot.len().div_ceil(SECTOR_SIZE) as u64;
+    let mut sorted = sectors.to_vec();
+    sorted.sort_unstable();
+    for pair in sorted.windows(2) {
+        if copy_end(pair[0], boot_sectors)? > pair[1] {
+            return Err(Error::Layout(format!(
+                "Boot image copies at sectors {} and {} overlap",
+                pair[0], pair[1]
+            )));
+        }
+    }
+    let Some(last) = sorted.last() else {
+        return Err(Error::Layout("No boot sector given".into()));
+    };
+    if sorted[0] == 0 {
+        return Err(Error::Layout(
+            "Sector 0 holds the partition table and cannot hold the boot image".into(),
+        ));
+    }
+
+    let len = usize::try_from(copy_end(*last, boot_sectors)?)
+        .ok()
+        .and_then(|end| end.checked_mul(SECTOR_SIZE))
+        .ok_or_else(|| Error::Layout(format!("Boot image copy at sector {last} is too large")))?;
+    let mut image = vec![0u8; len];
+    for sector in sorted {
+        let offset = sector as usize * SECTOR_SIZE;
+        image[offset..offset + boot.len()].copy_from_slice(boot);
+    }
+    Ok(image)
+}
+
+/// First sector after the copy of a `boot_sectors` long boot image at `sector`.
+fn copy_end(sector: u64, boot_sectors: u64) -> Result<u64> {
+    sector
+        .checked_add(boot_sectors)
+        .filter(|end| *end <= SDMC_SECTOR_COUNT)
+        .ok_or_else(|| {
+            Error::Layout(format!(
+                "Boot image copy at sector {sector} is beyond the 32-bit sector range"
+            ))
+        })
+}
+
+/// Build an MBR disk image of `size` bytes with one FAT32 partition
+/// holding `boot` as `file_name` and a `bootcfg.txt` pointing to it.
+pub fn fat32_image(boot: &[u8], file_name: &str, size: u64) -> Result<Vec<u8>> {
+    check_boot_image(boot)?;
+    if file_name.eq_ignore_ascii_case(BOOT_CFG_NAME) || file_name.contains(['/', '\\']) {
+        return Err(Error::Layout(format!(
+            "Invalid boot file name {file_name:?}"
+        )));
+    }
+    let start = FAT32_PARTITION_SECTOR * SECTOR_SIZE as u64;
+    if size <= start || !size.is_multiple_of(SECTOR_SIZE as u64) || size > u32::MAX as u64 * 512 {
+        return Err(Error::Layout(format!("Invalid disk size {size:#x}")));
+    }
+
+    // Format the partition and add the boot files
+    let mut volume = Cursor::new(vec![0u8; (size - start) as usize]);
+    fatfs::format_volume(
+        &mut volume,
+        FormatVolumeOptions::new()
+            .fat_type(FatType::Fat32)
+            .volume_label(*b"AIC BOOT   "),
+    )
+    .map_err(fat_error)?;
+    {
+        let fs = FileSystem::new(&mut volume, FsOptions::new()).map_err(fat_error)?;
+        let boot_cfg = format!("boot0={file_name}\n");
+        fs.root_dir()
+            .create_file(BOOT_CFG_NAME)
+            .and_then(|mut f| f.write_all(boot_cfg.as_bytes()))
+            .map_err(fat_error)?;
+        fs.root_dir()
+            .create_file(file_name)
+            .and_then(|mut f| f.write_all(boot))
+            .map_err(fat_error)?;
+        fs.unmount().map_err(fat_error)?;
+    }
+
+    let mut disk = vec![0u8; start as usize];
+    disk.extend_from_slice(&volume.into_inner());
+
+    // MBR with a single LBA-addressed FAT32 partition
+    let entry = &mut disk[446..462];
+    entry[1..4].copy_from_slice(&[0xFE, 0xFF, 0xFF]);
+    entry[4] = 0x0C;
+    entry[5..8].copy_from_slice(&[0xFE, 0xFF, 0xFF]);
+    entry[8..12].copy_from_slice(&(FAT32_PARTITION_SECTOR as u32).to_le_bytes());
+    entry[12..16].copy_from_slice(&(((size - start) / SECTOR_SIZE as u64) as u32).to_le_bytes());
+    disk[510..512].copy_from_slice(&[0x55, 0xAA]);
+    Ok(disk)
+}
+
+/// Check that `boot` is a valid AIC image.
+fn check_boot_image(boot: &[u8]) -> Result<()> {
+    let verification = verify::verify_image(boot, None)?;
+    match verification.checks.into_iter().find(|c| c.result.is_err()) {
+        Some(check) => Err(Error::Layout(format!(
+            "Invalid boot image: {}: {}",
+            check.name,
+            check.result.unwrap_err()
+        ))),
+        None => Ok(()),
+    }
+}
+
+fn fat_error(e: std::io::Error) -> Error {
+    Error::Layout(format!("Failed to build FAT32 volume: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImageBuilder, PbpImage};
+    use std::io::Read;
+
+    fn boot_image() -> Vec<u8> {
+        let pbp = PbpImage::from_binary(b"PBP \0\0\0\0\x13\0\0\0").unwrap();
+        ImageBuilder::new(&pbp).firmware(&[7; 900]).build().unwrap()
+    }
+
+    #[test]
+    fn raw_sdmc_layout() {
+        let boot = boot_image();
+        let image = sdmc_image(&boot, &[SDMC_BOOT_SECTOR, 1058]).unwrap();
+        let primary = 34 * 512;
+        let backup = 1058 * 512;
+        assert!(image[..primary].iter().all(|b| *b == 0));
+        assert_eq!(&image[primary..primary + boot.len()], boot.as_slice());
+        assert_eq!(&image[backup..backup + boot.len()], boot.as_slice());
+        assert_eq!(image.len(), backup + boot.len().next_multiple_of(512));
+
+        assert!(sdmc_image(&boot, &[34, 35]).is_err());
+        assert!(sdmc_image(&boot, &[0]).is_err());
+        assert!(sdmc_image(&boot, &[]).is_err());
+        assert!(sdmc_image(&boot[..boot.len() - 4], &[34]).is_err());
+        assert!(sdmc_image(&boot, &[34, u64::MAX]).is_err());
+        assert!(sdmc_image(&boot, &[u64::MAX - 1, u64::MAX]).is_err());
+        assert!(sdmc_image(&boot, &[SDMC_SECTOR_COUNT - 1]).is_err());
+    }
+
+    #[test]
+    fn fat32_boot_medium() {
+        let boot = boot_image();
+        let disk = fat32_image(&boot, DEFAULT_BOOT_FILE_NAME, 64 << 20).unwrap();
+        assert_eq!(disk.len(), 64 << 20);
+        assert_eq!(&disk[510..512], &[0x55, 0xAA]);
+        assert_eq!(disk[446 + 4], 0x0C);
+
+        let volume = Cursor::new(disk[2048 * 512..].to_vec());
+        let fs = FileSystem::new(volume, FsOptions::new()).unwrap();
+        assert_eq!(fs.fat_type(), FatType::Fat32);
+        let root = fs.root_dir();
+        let mut boot_cfg = String::new();
+        root.open_file(BOOT_CFG_NAME)
+            .unwrap()
+            .read_to_string(&mut boot_cfg)
+            .unwrap();
+        assert_eq!(boot_cfg, "boot0=bootloader.aic\n");
+        let mut stored = Vec::new();
+        root.open_file(DEFAULT_BOOT_FILE_NAME)
+            .unwrap()
+            .read_to_end(&mut stored)
+            .unwrap();
+        assert_eq!(stored, boot);
+
+        assert!(fat32_image(&boot, "a/b.aic", 64 << 20).is_err());
+        assert!(fat32_image(&boot, "boot.aic", 1 << 20).is_err());
+    }
+}
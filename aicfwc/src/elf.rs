@@ -0,0 +1,269 @@
+//! ELF input support.
+
+use object::elf::{EM_RISCV, PT_LOAD};
+use object::read::elf::{ElfFile, FileHeader, ProgramHeader};
+use object::{Endianness, Object, ObjectSection};
+
+use crate::error::{Error, Result};
+
+/// Largest flattened image accepted, catches segments that are far apart.
+const MAX_IMAGE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Loadable segments of an ELF file flattened into one binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElfImage {
+    /// Load address of the first byte of `data`.
+    pub load_address: u32,
+    /// ELF entry point.
+    pub entry_point: u32,
+    /// Segment contents with gaps filled with zeros.
+    pub data: Vec<u8>,
+}
+
+/// Check whether `data` starts with the ELF magic.
+pub fn is_elf(data: &[u8]) -> bool {
+    data.starts_with(b"\x7FELF")
+}
+
+/// Flatten the `PT_LOAD` segments of a RISC-V ELF file.
+///
+/// Segments are placed at their physical (load) addresses,
+/// so initialized data is put where the runtime copies it from.
+pub fn flatten(data: &[u8]) -> Result<ElfImage> {
+    match object::FileKind::parse(data).map_err(parse_error)? {
+        object::FileKind::Elf32 => flatten_elf::<object::elf::FileHeader32<Endianness>>(data),
+        object::FileKind::Elf64 => flatten_elf::<object::elf::FileHeader64<Endianness>>(data),
+        kind => Err(Error::Elf(format!("Unsupported input file kind {kind:?}"))),
+    }
+}
+
+fn flatten_elf<Elf: FileHeader<Endian = Endianness>>(data: &[u8]) -> Result<ElfImage> {
+    let elf = ElfFile::<Elf>::parse(data).map_err(parse_error)?;
+    let endian = elf.endian();
+    if elf.elf_header().e_machine(endian) != EM_RISCV {
+        return Err(Error::Elf("ELF file is not a RISC-V executable".into()));
+    }
+
+    // Collect non-empty loadable segments as (load address, contents)
+    let mut segments = Vec::new();
+    for ph in elf.elf_program_headers() {
+        if ph.p_type(endian) != PT_LOAD || ph.p_filesz(endian).into() == 0 {
+            continue;
+        }
+        let paddr: u64 = ph.p_paddr(endian).into();
+        let bytes = ph
+            .data(endian, data)
+            .map_err(|_| Error::Elf("ELF segment data out of range".into()))?;
+        segments.push((paddr, bytes));
+    }
+    segments.sort_by_key(|(paddr, _)| *paddr);
+
+    let Some(&(start, _)) = segments.first() else {
+        return Err(Error::Elf("ELF file has no loadable segments".into()));
+    };
+    // Reject overlaps before sizing the image, a segment nested in a larger
+    // one would otherwise be taken as the end of the image.
+    let mut end = start;
+    for (paddr, bytes) in &segments {
+        if *paddr < end {
+            return Err(Error::Elf(format!(
+                "ELF segment at {paddr:#x} overlaps the previous segment"
+            )));
+        }
+        end = paddr + bytes.len() as u64;
+    }
+    if end > u64::from(u32::MAX) + 1 {
+        return Err(Error::Elf(
+            "ELF segments exceed the 32-bit address space".into(),
+        ));
+    }
+    if end - start > MAX_IMAGE_SIZE {
+        return Err(Error::Elf(format!(
+            "ELF segments span {:#x} bytes, too sparse to flatten",
+            end - start
+        )));
+    }
+
+    let mut image = vec![0u8; (end - start) as usize];
+    for (paddr, bytes) in segments {
+        let offset = (paddr - start) as usize;
+        image[offset..offset + bytes.len()].copy_from_slice(bytes);
+    }
+
+    Ok(ElfImage {
+        load_address: start as u32,
+        entry_point: u32::try_from(elf.entry())
+            .map_err(|_| Error::Elf("ELF entry point exceeds 32 bits".into()))?,
+        data: image,
+    })
+}
+
+/// Check that the flattened image starts with the PBP header section.
+///
+/// `artinchip-rt` links the `.head.pbp` input section into the `.head`
+/// output section right in front of `.text`.
+pub fn check_pbp_header(data: &[u8], image: &ElfImage) -> Result<()> {
+    let elf = object::File::parse(data).map_err(parse_error)?;
+    let head = elf
+        .section_by_name(".head")
+        .or_else(|| elf.section_by_name(".head.pbp"))
+        .ok_or_else(|| Error::Elf("ELF file has no `.head.pbp` PBP header section".into()))?;
+    let contents = head
+        .data()
+        .map_err(|_| Error::Elf("Failed to read PBP header section".into()))?;
+
+    if head.address() != u64::from(image.load_address) {
+        return Err(Error::Elf(format!(
+            "PBP header section at {:#x} is not the start of the image at {:#x}",
+            head.address(),
+            image.load_address
+        )));
+    }
+    if !contents.starts_with(b"PBP ") || !image.data.starts_with(contents) {
+        return Err(Error::Elf(
+            "PBP header section does not contain the \"PBP \" magic".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn parse_error(e: object::Error) -> Error {
+    Error::Elf(format!("Failed to parse ELF file: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal little-endian ELF32 RISC-V executable.
+    ///
+    /// Each segment is `(paddr, contents)`, the first segment is also
+    /// described by a `.head` section.
+    fn build_elf(entry: u32, segments: &[(u32, &[u8])]) -> Vec<u8> {
+        const EHDR: usize = 52;
+        const PHDR: usize = 32;
+        const SHDR: usize = 40;
+        let shstrtab = b"\0.head\0.shstrtab\0";
+
+        let mut data_offset = EHDR + PHDR * segments.len();
+        let mut payload = Vec::new();
+        let mut phdrs = Vec::new();
+        for (paddr, bytes) in segments {
+            for v in [
+                1,
+                data_offset as u32,
+                *paddr,
+                *paddr,
+                bytes.len() as u32,
+                bytes.len() as u32,
+                5,
+                4,
+            ] {
+                phdrs.extend_from_slice(&v.to_le_bytes());
+            }
+            payload.extend_from_slice(bytes);
+            data_offset += bytes.len();
+        }
+        let shstrtab_offset = data_offset;
+        let shoff = (shstrtab_offset + shstrtab.len()).next_multiple_of(4);
+
+        let mut elf = Vec::new();
+        elf.extend_from_slice(b"\x7FELF\x01\x01\x01\0\0\0\0\0\0\0\0\0");
+        elf.extend_from_slice(&2u16.to_le_bytes()); // ET_EXEC
+        elf.extend_from_slice(&EM_RISCV.to_le_bytes());
+        elf.extend_from_slice(&1u32.to_le_bytes());
+        elf.extend_from_slice(&entry.to_le_bytes());
+        elf.extend_from_slice(&(EHDR as u32).to_le_bytes());
+        elf.extend_from_slice(&(shoff as u32).to_le_bytes());
+        elf.extend_from_slice(&0u32.to_le_bytes());
+        for v in [EHDR, PHDR, segments.len(), SHDR, 3, 2] {
+            elf.extend_from_slice(&(v as u16).to_le_bytes());
+        }
+        elf.extend_from_slice(&phdrs);
+        elf.extend_from_slice(&payload);
+        elf.extend_from_slice(shstrtab);
+        elf.resize(shoff, 0);
+
+        let (head_addr, head_bytes) = segments[0];
+        let sections: [[u32; 10]; 3] = [
+            [0; 10],
+            [
+                1,
+                1,
+                2,
+                head_addr,
+                EHDR as u32 + PHDR as u32 * segments.len() as u32,
+                8.min(head_bytes.len() as u32),
+                0,
+                0,
+                4,
+                0,
+            ],
+            [
+                7,
+                3,
+                0,
+                0,
+                shstrtab_offset as u32,
+                shstrtab.len() as u32,
+                0,
+                0,
+                1,
+                0,
+            ],
+        ];
+        for section in sections {
+            for v in section {
+                elf.extend_from_slice(&v.to_le_bytes());
+            }
+        }
+        elf
+    }
+
+    #[test]
+    fn flatten_pbp_elf() {
+        let head = b"PBP \0\0\0\0";
+        let text = [0x13u8, 0, 0, 0, 0x6F, 0, 0, 0];
+        let data = [0xAAu8; 6];
+        let mut first = head.to_vec();
+        first.extend_from_slice(&text);
+        let elf = build_elf(0x3004_4000, &[(0x3004_3FF8, &first), (0x3004_4010, &data)]);
+        assert!(is_elf(&elf));
+
+        let image = flatten(&elf).unwrap();
+        assert_eq!(image.load_address, 0x3004_3FF8);
+        assert_eq!(image.entry_point, 0x3004_4000);
+        assert_eq!(image.data.len(), 0x1E);
+        assert_eq!(&image.data[..16], first.as_slice());
+        assert_eq!(&image.data[16..24], &[0; 8]);
+        assert_eq!(&image.data[24..], &data);
+        check_pbp_header(&elf, &image).unwrap();
+    }
+
+    #[test]
+    fn reject_missing_pbp_header() {
+        let elf = build_elf(0x3010_0000, &[(0x3010_0000, &[0x13, 0, 0, 0, 0, 0, 0, 0])]);
+        let image = flatten(&elf).unwrap();
+        assert!(check_pbp_header(&elf, &image).is_err());
+    }
+
+    #[test]
+    fn reject_overlapping_segments() {
+        let elf = build_elf(0, &[(0x1000, &[1; 16]), (0x1008, &[2; 16])]);
+        assert!(flatten(&elf).is_err());
+    }
+
+    #[test]
+    fn reject_nested_segments() {
+        let elf = build_elf(0, &[(0x1000, &[1; 64]), (0x1010, &[2; 8])]);
+        assert!(matches!(flatten(&elf), Err(Error::Elf(_))));
+        let elf = build_elf(0, &[(0x1000, &[1; 16]), (0x1000, &[2; 8])]);
+        assert!(matches!(flatten(&elf), Err(Error::Elf(_))));
+    }
+
+    #[test]
+    fn reject_raw_binary() {
+        assert!(!is_elf(b"PBP \0\0\0\0"));
+        assert!(flatten(b"PBP \0\0\0\0").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+//! Error types.
+
+use std::{fmt, io, path::PathBuf};
+
+/// Errors of building and parsing AIC images.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading a file failed.
+    Io { path: PathBuf, source: io::Error },
+    /// Data is shorter than the structure it should hold.
+    Truncated {
+        what: &'static str,
+        len: usize,
+        needed: usize,
+    },
+    /// Magic number mismatch.
+    BadMagic { what: &'static str, found: [u8; 4] },
+    /// Word sum is not 0xFFFFFFFF.
+    BadChecksum { what: &'static str },
+    /// Length is not 4-byte aligned or out of range.
+    BadLength { what: &'static str, len: usize },
+    /// A HEAD2 region lies outside the image body.
+    BadRegion {
+        name: &'static str,
+        offset: u32,
+        len: u32,
+        image_len: usize,
+    },
+    /// HEAD2 holds an algorithm this crate does not support.
+    Unsupported { what: &'static str, value: u32 },
+    /// Signature or digest mismatch, or a malformed sign result.
+    Signature(String),
+    /// Invalid or unsupported key.
+    Key(String),
+    /// Encryption or decryption failed.
+    Crypto(String),
+    /// Invalid ELF input.
+    Elf(String),
+    /// Invalid TOML config or private data.
+    Config(String),
+    /// Invalid firmware version.
+    Version(String),
+    /// Invalid flash or disk layout.
+    Layout(String),
+}
+
+/// Result type of this crate.
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "Failed to read {path:?}: {source}"),
+            Error::Truncated { what, len, needed } => {
+                write!(f, "{what} too short: {len} bytes, needs {needed}")
+            }
+            Error::BadMagic { what, found } => write!(
+                f,
+                "{what} magic mismatch, found {:?}",
+                String::from_utf8_lossy(found)
+            ),
+            Error::BadChecksum { what } => write!(f, "{what} word sum is not 0xFFFFFFFF"),
+            Error::BadLength { what, len } => write!(f, "Invalid {what} length {len:#x}"),
+            Error::BadRegion {
+                name,
+                offset,
+                len,
+                image_len,
+            } => write!(
+                f,
+                "{name} {offset:#x}+{len:#x} is outside the image body 0x100..{image_len:#x}"
+            ),
+            Error::Unsupported { what, value } => write!(f, "Unsupported {what} {value}"),
+            Error::Signature(msg)
+            | Error::Key(msg)
+            | Error::Crypto(msg)
+            | Error::Elf(msg)
+            | Error::Config(msg)
+            | Error::Version(msg)
+            | Error::Layout(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Read a whole file, attaching its path to the error.
+pub(crate) fn read(path: &std::path::Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|source| Error::Io {
+        path: path.to_owned(),
+        source,
+    })
+}
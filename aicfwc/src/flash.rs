@@ -0,0 +1,399 @@
+//! SPI NOR / SPI NAND flash image builder.
+//!
+//! A TOML config describes the flash device and its partitions:
+//!
+//! ```toml
+//! [flash]
+//! type = "spi-nor"
+//! size = 0x100_0000
+//! erase_size = 0x1000
+//!
+//! [[partition]]
+//! name = "boot"
+//! offset = 0
+//! size = 0x4_0000
+//! file = "pbp.pk_pbp"
+//!
+//! [[partition]]
+//! name = "boot_backup"
+//! offset = 0x4_0000
+//! size = 0x4_0000
+//! file = "pbp.pk_pbp"
+//!
+//! [[partition]]
+//! name = "app"
+//! offset = 0x8_0000
+//! file = "app.bin"
+//! erase_align = 0x1_0000
+//! ```
+//!
+//! The partition at offset 0 must hold a valid AIC boot image. A partition
+//! without `size` extends to the next partition or the end of the flash.
+//! Unused space is left erased (0xFF).
+//!
+//! For SPI NAND (`type = "spi-nand"`, with `page_size`, `spare_size`,
+//! `pages_per_block`, `blocks` and `bad_blocks`) offsets are in data bytes.
+//! Every page is followed by its erased spare area in the output, and the
+//! contents of a partition skip the listed bad blocks, which stay erased.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::error::{self, Error, Result};
+use crate::verify;
+
+/// Value of erased flash.
+const ERASED: u8 = 0xFF;
+
+/// Flash image config file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlashConfig {
+    pub flash: FlashDevice,
+    #[serde(rename = "partition")]
+    pub partitions: Vec<Partition>,
+}
+
+/// Flash device geometry.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum FlashDevice {
+    SpiNor {
+        /// Total size in bytes.
+        size: u32,
+        /// Smallest erasable unit in bytes.
+        #[serde(default = "default_nor_erase_size")]
+        erase_size: u32,
+    },
+    SpiNand {
+        /// Data bytes per page.
+        page_size: u32,
+        /// Spare (OOB) bytes per page.
+        #[serde(default)]
+        spare_size: u32,
+        pages_per_block: u32,
+        /// Total number of blocks.
+        blocks: u32,
+        /// Blocks that are skipped when placing partition contents.
+        #[serde(default)]
+        bad_blocks: Vec<u32>,
+    },
+}
+
+const fn default_nor_erase_size() -> u32 {
+    0x1000
+}
+
+impl FlashDevice {
+    /// Data size of the device in bytes.
+    fn size(&self) -> u64 {
+        match self {
+            FlashDevice::SpiNor { size, .. } => u64::from(*size),
+            FlashDevice::SpiNand { blocks, .. } => u64::from(*blocks) * self.erase_size(),
+        }
+    }
+
+    /// Erase unit in bytes: a sector on NOR, a block on NAND.
+    fn erase_size(&self) -> u64 {
+        match self {
+            FlashDevice::SpiNor { erase_size, .. } => u64::from(*erase_size),
+            FlashDevice::SpiNand {
+                page_size,
+                pages_per_block,
+                ..
+            } => u64::from(*page_size) * u64::from(*pages_per_block),
+        }
+    }
+
+    /// Whether the erase unit at data offset `offset` is a bad block.
+    fn is_bad(&self, offset: u64) -> bool {
+        match self {
+            FlashDevice::SpiNor { .. } => false,
+            FlashDevice::SpiNand { bad_blocks, .. } => {
+                bad_blocks.contains(&((offset / self.erase_size()) as u32))
+            }
+        }
+    }
+}
+
+/// A partition of the flash image.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Partition {
+    pub name: String,
+    pub offset: u32,
+    /// Defaults to the space up to the next partition.
+    pub size: Option<u32>,
+    /// Contents, relative to the config file; the partition is erased if absent.
+    pub file: Option<PathBuf>,
+    /// Alignment of offset and size, defaults to the erase unit.
+    pub erase_align: Option<u32>,
+}
+
+impl FlashConfig {
+    /// Parse a flash image config.
+    pub fn parse(text: &str) -> Result<Self> {
+        toml::from_str(text)
+            .map_err(|e| Error::Config(format!("Failed to parse flash config: {e}")))
+    }
+}
+
+/// Build the flash image described by the config file at `path`.
+pub fn build_from_file(path: &Path) -> Result<Vec<u8>> {
+    let text = String::from_utf8(error::read(path)?)
+        .map_err(|_| Error::Config(format!("Flash config {path:?} is not UTF-8")))?;
+    let config = FlashConfig::parse(&text)?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    let contents = config
+        .partitions
+        .iter()
+        .map(|p| match &p.file {
+            Some(file) => error::read(&base.join(file)),
+            None => Ok(Vec::new()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    build(&config, &contents)
+}
+
+/// Build a flash image, `contents[i]` is the data of partition `i`.
+pub fn build(config: &FlashConfig, contents: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let device = &config.flash;
+    let flash_size = device.size();
+    let erase_size = device.erase_size();
+    if flash_size == 0 || erase_size == 0 || flash_size > u64::from(u32::MAX) + 1 {
+        return Err(Error::Layout(format!(
+            "Invalid flash geometry: size {flash_size:#x}, erase unit {erase_size:#x}"
+        )));
+    }
+    if let FlashDevice::SpiNand { bad_blocks, .. } = device
+        && bad_blocks.contains(&0)
+    {
+        return Err(Error::Layout(
+            "Block 0 holds the boot image and cannot be bad".into(),
+        ));
+    }
+
+    let mut data = vec![ERASED; flash_size as usize];
+    let mut boot_found = false;
+    let mut end_of_previous = 0;
+    for (i, (partition, contents)) in config.partitions.iter().zip(contents).enumerate() {
+        let name = &partition.name;
+        let offset = u64::from(partition.offset);
+        let next = config
+            .partitions
+            .get(i + 1)
+            .map_or(flash_size, |p| u64::from(p.offset));
+        let size = partition
+            .size
+            .map_or(next.saturating_sub(offset), u64::from);
+        let align = partition.erase_align.map_or(erase_size, u64::from);
+
+        if align == 0 || !align.is_multiple_of(erase_size) {
+            return Err(Error::Layout(format!(
+                "Partition `{name}` erase alignment {align:#x} is not a multiple of the erase unit {erase_size:#x}"
+            )));
+        }
+        if !offset.is_multiple_of(align) || !size.is_multiple_of(align) {
+            return Err(Error::Layout(format!(
+                "Partition `{name}` {offset:#x}+{size:#x} is not aligned to {align:#x} bytes"
+            )));
+        }
+        if size == 0 {
+            return Err(Error::Layout(format!("Partition `{name}` is empty")));
+        }
+        if offset < end_of_previous {
+            return Err(Error::Layout(format!(
+                "Partition `{name}` at {offset:#x} overlaps the previous partition"
+            )));
+        }
+        if offset + size > flash_size {
+            return Err(Error::Layout(format!(
+                "Partition `{name}` {offset:#x}+{size:#x} exceeds the flash size {flash_size:#x}"
+            )));
+        }
+        end_of_previous = offset + size;
+
+        if offset == 0 {
+            check_boot_image(name, contents)?;
+            boot_found = true;
+        }
+
+        // Copy contents erase unit by erase unit, skipping bad blocks
+        let mut chunks = contents.chunks(erase_size as usize);
+        let mut chunk = chunks.next();
+        for block in (offset..offset + size).step_by(erase_size as usize) {
+            let Some(bytes) = chunk else {
+                break;
+            };
+            if device.is_bad(block) {
+                continue;
+            }
+            data[block as usize..block as usize + bytes.len()].copy_from_slice(bytes);
+            chunk = chunks.next();
+        }
+        if chunk.is_some() {
+            return Err(Error::Layout(format!(
+                "File of partition `{name}` is {} bytes, larger than the usable partition size",
+                contents.len()
+            )));
+        }
+    }
+    if !boot_found {
+        return Err(Error::Layout(
+            "No partition at offset 0 holds the boot image".into(),
+        ));
+    }
+
+    Ok(match *device {
+        FlashDevice::SpiNor { .. } => data,
+        FlashDevice::SpiNand {
+            page_size,
+            spare_size,
+            ..
+        } => {
+            let (page_size, spare_size) = (page_size as usize, spare_size as usize);
+            let mut raw = Vec::with_capacity(data.len() / page_size * (page_size + spare_size));
+            for page in data.chunks(page_size) {
+                raw.extend_from_slice(page);
+                raw.resize(raw.len() + spare_size, ERASED);
+            }
+            raw
+        }
+    })
+}
+
+/// Check that the boot partition holds a valid AIC image with a PBP at 0x100.
+fn check_boot_image(name: &str, contents: &[u8]) -> Result<()> {
+    if !contents.starts_with(b"AIC ") || contents.get(0x100..0x104) != Some(b"PBP ") {
+        return Err(Error::Layout(format!(
+            "Partition `{name}` at offset 0 does not hold an AIC boot image with a PBP"
+        )));
+    }
+    let verification = verify::verify_image(contents, None)?;
+    if let Some(check) = verification.checks.iter().find(|c| c.result.is_err()) {
+        return Err(Error::Layout(format!(
+            "Boot image of partition `{name}` is invalid: {}: {}",
+            check.name,
+            check.result.as_ref().unwrap_err()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ImageBuilder, PbpImage};
+
+    fn boot_image() -> Vec<u8> {
+        let pbp = PbpImage::from_binary(b"PBP \0\0\0\0\x13\0\0\0").unwrap();
+        ImageBuilder::new(&pbp).build().unwrap()
+    }
+
+    const NOR_CONFIG: &str = r#"
+        [flash]
+        type = "spi-nor"
+        size = 0x40000
+
+        [[partition]]
+        name = "boot"
+        offset = 0
+        size = 0x10000
+        file = "boot.pk_pbp"
+
+        [[partition]]
+        name = "boot_backup"
+        offset = 0x10000
+        size = 0x10000
+        file = "boot.pk_pbp"
+
+        [[partition]]
+        name = "app"
+        offset = 0x20000
+        file = "app.bin"
+        erase_align = 0x10000
+    "#;
+
+    #[test]
+    fn build_nor_image() {
+        let config = FlashConfig::parse(NOR_CONFIG).unwrap();
+        let boot = boot_image();
+        let app = vec![0x5A; 0x1234];
+        let image = build(&config, &[boot.clone(), boot.clone(), app.clone()]).unwrap();
+
+        assert_eq!(image.len(), 0x40000);
+        assert_eq!(&image[..4], b"AIC ");
+        assert_eq!(&image[0x100..0x104], b"PBP ");
+        assert_eq!(&image[..boot.len()], boot.as_slice());
+        assert_eq!(&image[0x10000..0x10000 + boot.len()], boot.as_slice());
+        assert_eq!(&image[0x20000..0x20000 + app.len()], app.as_slice());
+        assert!(image[0x20000 + app.len()..].iter().all(|b| *b == ERASED));
+    }
+
+    #[test]
+    fn reject_invalid_layouts() {
+        let boot = boot_image();
+        let contents = [boot.clone(), boot.clone(), vec![0; 16]];
+
+        let misaligned = NOR_CONFIG.replace("offset = 0x20000", "offset = 0x28000");
+        let config = FlashConfig::parse(&misaligned).unwrap();
+        assert!(build(&config, &contents).is_err());
+
+        let overlapping = NOR_CONFIG.replace("offset = 0x10000", "offset = 0x8000");
+        let config = FlashConfig::parse(&overlapping).unwrap();
+        assert!(build(&config, &contents).is_err());
+
+        let config = FlashConfig::parse(NOR_CONFIG).unwrap();
+        let too_large = [boot.clone(), boot.clone(), vec![0; 0x20001]];
+        assert!(build(&config, &too_large).is_err());
+
+        let mut corrupted = boot.clone();
+        corrupted[0x104] ^= 1;
+        assert!(build(&config, &[corrupted, boot, vec![]]).is_err());
+    }
+
+    #[test]
+    fn build_nand_image_skipping_bad_blocks() {
+        let config = FlashConfig::parse(
+            r#"
+            [flash]
+            type = "spi-nand"
+            page_size = 2048
+            spare_size = 64
+            pages_per_block = 2
+            blocks = 8
+            bad_blocks = [3]
+
+            [[partition]]
+            name = "boot"
+            offset = 0
+            size = 0x2000
+            file = "boot.pk_pbp"
+
+            [[partition]]
+            name = "app"
+            offset = 0x2000
+            file = "app.bin"
+            "#,
+        )
+        .unwrap();
+        let boot = boot_image();
+        let app: Vec<u8> = (0..0x2800u32).map(|i| (i / 0x1000) as u8).collect();
+        let image = build(&config, &[boot.clone(), app]).unwrap();
+
+        let page = |n: usize| &image[n * 2112..n * 2112 + 2048];
+        let spare = |n: usize| &image[n * 2112 + 2048..(n + 1) * 2112];
+        assert_eq!(image.len(), 16 * 2112);
+        assert_eq!(&page(0)[..boot.len()], boot.as_slice());
+        assert!(spare(0).iter().all(|b| *b == ERASED));
+
+        // Block 2 holds app[0..0x1000], bad block 3 is skipped
+        assert!(page(4).iter().all(|b| *b == 0));
+        assert!(page(6).iter().all(|b| *b == ERASED));
+        assert!(page(8).iter().all(|b| *b == 1));
+        assert!(page(10)[..0x800].iter().all(|b| *b == 2));
+
+        // Only five good blocks are left for the application
+        assert!(build(&config, &[boot, vec![0; 0x5001]]).is_err());
+    }
+}
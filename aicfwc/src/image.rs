@@ -0,0 +1,1011 @@
+//! AIC boot image model.
+//!
+//! An AIC image is laid out as:
+//!
+//! ```text
+//! [0x000..0x008] = HEAD1: b"AIC " + checksum
+//! [0x008..0x100] = HEAD2: image description
+//! [0x100..]      = DATA1: PBP, zero padded to 256 bytes
+//! [..]           = DATA2: firmware, zero padded to 256 bytes
+//! [..]           = PRIV:  private data, zero padded to 4 bytes
+//! [..]           = IV:    AES IV of an encrypted image
+//! [..image_len]  = SIGN:  MD5 digest or RSA signature of HEAD2..IV
+//! ```
+//!
+//! The word sum of the whole image is 0xFFFFFFFF, and so is the word sum
+//! of the PBP. See the [`sign`] and [`crypt`]
+//! modules for the layout of signed and encrypted images.
+
+use md5::{Digest, Md5};
+use rsa::RsaPrivateKey;
+use std::{fmt, ops::Range, path::Path};
+
+use crate::crypt::{self, AesKey};
+use crate::error::{self, Error, Result};
+use crate::sign;
+
+/// Size of HEAD1.
+pub const HEAD1_SIZE: usize = 8;
+/// Size of HEAD2.
+pub const HEAD2_SIZE: usize = 248;
+/// Size of HEAD1 + HEAD2, also the offset of DATA1.
+pub const HEADER_SIZE: usize = HEAD1_SIZE + HEAD2_SIZE;
+/// Alignment of DATA1 and DATA2.
+pub const SECTION_ALIGN: usize = 256;
+/// Length of the MD5 sign result of an unsigned image.
+pub const MD5_LEN: usize = 16;
+/// Word sum of a valid image or PBP.
+pub const CHECKSUM_TARGET: u32 = 0xFFFF_FFFF;
+
+/// Sum of the little-endian 32-bit words of `data`, wrapping.
+///
+/// Trailing bytes that do not form a whole word are ignored.
+pub fn word_sum(data: &[u8]) -> u32 {
+    data.chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .fold(0, u32::wrapping_add)
+}
+
+/// Check that `data` is word aligned and its word sum is [`CHECKSUM_TARGET`].
+pub fn verify_checksum(data: &[u8]) -> bool {
+    data.len().is_multiple_of(4) && word_sum(data) == CHECKSUM_TARGET
+}
+
+fn word(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// HEAD1: magic and checksum of the whole image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Head1 {
+    pub magic: [u8; 4],
+    /// Chosen so that the word sum of the image is 0xFFFFFFFF.
+    pub checksum: u32,
+}
+
+impl Head1 {
+    /// Magic of an AIC image.
+    pub const MAGIC: [u8; 4] = *b"AIC ";
+
+    /// Parse HEAD1 at the start of `data`, the magic is not checked.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < HEAD1_SIZE {
+            return Err(Error::Truncated {
+                what: "HEAD1",
+                len: data.len(),
+                needed: HEAD1_SIZE,
+            });
+        }
+        Ok(Self {
+            magic: [data[0], data[1], data[2], data[3]],
+            checksum: word(data, 4),
+        })
+    }
+
+    /// Encode HEAD1.
+    pub fn to_bytes(&self) -> [u8; HEAD1_SIZE] {
+        let mut out = [0; HEAD1_SIZE];
+        out[0..4].copy_from_slice(&self.magic);
+        out[4..8].copy_from_slice(&self.checksum.to_le_bytes());
+        out
+    }
+}
+
+/// Offset and length of a HEAD2 region, relative to the image start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Region {
+    pub offset: u32,
+    pub len: u32,
+}
+
+impl Region {
+    /// Create a region.
+    pub const fn new(offset: u32, len: u32) -> Self {
+        Self { offset, len }
+    }
+
+    /// Whether the region is empty (unused).
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Byte range of the region, checked to lie in the body of an
+    /// image of `image_len` bytes.
+    pub fn range(&self, name: &'static str, image_len: usize) -> Result<Range<usize>> {
+        let (offset, len) = (self.offset as usize, self.len as usize);
+        match offset.checked_add(len) {
+            Some(end) if offset >= HEADER_SIZE && end <= image_len => Ok(offset..end),
+            _ => Err(Error::BadRegion {
+                name,
+                offset: self.offset,
+                len: self.len,
+                image_len,
+            }),
+        }
+    }
+}
+
+/// HEAD2: description of the image read by the BROM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Head2 {
+    pub header_version: u32,
+    pub image_len: u32,
+    pub fw_version: FwVersion,
+    /// Length of the DATA2 firmware.
+    pub loader_len: u32,
+    /// Address the BROM loads DATA2 to.
+    pub load_address: u32,
+    /// Address the BROM jumps to after loading DATA2.
+    pub entry_point: u32,
+    /// See [`sign::SIGN_ALGO_MD5`] and [`sign::SIGN_ALGO_RSA2048`].
+    pub sign_algo: u32,
+    /// See [`crypt::ENC_ALGO_NONE`] and [`crypt::ENC_ALGO_AES128_CBC`].
+    pub enc_algo: u32,
+    pub sign: Region,
+    pub key: Region,
+    pub iv: Region,
+    pub private_data: Region,
+    pub pbp: Region,
+}
+
+impl Head2 {
+    /// Header version written by this crate.
+    pub const VERSION: u32 = 0x0001_0001;
+
+    /// Parse HEAD2 at the start of `data`.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < HEAD2_SIZE {
+            return Err(Error::Truncated {
+                what: "HEAD2",
+                len: data.len(),
+                needed: HEAD2_SIZE,
+            });
+        }
+        let word = |offset| word(data, offset);
+        let region = |offset| Region::new(word(offset), word(offset + 4));
+        Ok(Self {
+            header_version: word(0),
+            image_len: word(4),
+            fw_version: FwVersion::from_raw(word(8)),
+            loader_len: word(12),
+            load_address: word(16),
+            entry_point: word(20),
+            sign_algo: word(24),
+            enc_algo: word(28),
+            sign: region(32),
+            key: region(40),
+            iv: region(48),
+            private_data: region(56),
+            pbp: region(64),
+        })
+    }
+
+    /// Encode HEAD2, reserved bytes are zero.
+    pub fn to_bytes(&self) -> [u8; HEAD2_SIZE] {
+        let mut out = [0; HEAD2_SIZE];
+        let words = [
+            self.header_version,
+            self.image_len,
+            self.fw_version.to_raw(),
+            self.loader_len,
+            self.load_address,
+            self.entry_point,
+            self.sign_algo,
+            self.enc_algo,
+        ];
+        let regions = [self.sign, self.key, self.iv, self.private_data, self.pbp]
+            .into_iter()
+            .flat_map(|r| [r.offset, r.len]);
+        for (chunk, value) in out
+            .chunks_exact_mut(4)
+            .zip(words.into_iter().chain(regions))
+        {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        out
+    }
+
+    /// Check that every used region lies in the body of an image of `image_len` bytes.
+    pub fn check_regions(&self, image_len: usize) -> Result<()> {
+        for (name, region) in [
+            ("Encryption key", self.key),
+            ("IV", self.iv),
+            ("Private data", self.private_data),
+        ] {
+            if !region.is_empty() {
+                region.range(name, image_len)?;
+            }
+        }
+        self.pbp.range("PBP", image_len)?;
+        self.sign.range("Sign result", image_len)?;
+        let firmware = self.firmware_range();
+        Region::new(firmware.start as u32, firmware.len() as u32).range("Firmware", image_len)?;
+        Ok(())
+    }
+
+    /// Byte range of DATA1 + DATA2, the encrypted payload of an encrypted image.
+    pub fn payload_range(&self) -> Range<usize> {
+        let start = self.pbp.offset as usize;
+        let len = (self.pbp.len as usize).div_ceil(SECTION_ALIGN) * SECTION_ALIGN
+            + (self.loader_len as usize).div_ceil(SECTION_ALIGN) * SECTION_ALIGN;
+        start..start + len
+    }
+
+    /// Byte range of the DATA2 firmware.
+    pub fn firmware_range(&self) -> Range<usize> {
+        let start = self.pbp.offset as usize
+            + (self.pbp.len as usize).div_ceil(SECTION_ALIGN) * SECTION_ALIGN;
+        start..start + self.loader_len as usize
+    }
+}
+
+/// Firmware version field of HEAD2.
+///
+/// Stored as bytes `[anti_rollback, revision, minor, major]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FwVersion {
+    pub major: u8,
+    pub minor: u8,
+    pub revision: u8,
+    pub anti_rollback: u8,
+}
+
+impl FwVersion {
+    /// Decode from the raw HEAD2 word.
+    pub const fn from_raw(raw: u32) -> Self {
+        let [anti_rollback, revision, minor, major] = raw.to_le_bytes();
+        Self {
+            major,
+            minor,
+            revision,
+            anti_rollback,
+        }
+    }
+
+    /// Encode into the raw HEAD2 word.
+    pub const fn to_raw(self) -> u32 {
+        u32::from_le_bytes([self.anti_rollback, self.revision, self.minor, self.major])
+    }
+
+    /// Read the `package.version` of a `Cargo.toml` manifest.
+    pub fn from_manifest(path: &Path) -> Result<Self> {
+        let text = String::from_utf8(error::read(path)?)
+            .map_err(|_| Error::Config(format!("Manifest {path:?} is not UTF-8")))?;
+        let manifest: toml::Table = toml::from_str(&text)
+            .map_err(|e| Error::Config(format!("Failed to parse manifest {path:?}: {e}")))?;
+        let version = manifest
+            .get("package")
+            .and_then(|p| p.get("version"))
+            .ok_or_else(|| Error::Config(format!("Manifest {path:?} has no package version")))?;
+        let version = version.as_str().ok_or_else(|| {
+            Error::Config(format!(
+                "Package version in {path:?} is inherited, pass --fw-version instead"
+            ))
+        })?;
+        version.parse()
+    }
+}
+
+impl std::str::FromStr for FwVersion {
+    type Err = Error;
+
+    /// Parse `major.minor.revision`, a semver pre-release or build suffix is ignored.
+    fn from_str(s: &str) -> Result<Self> {
+        let core = s.split(['-', '+']).next().unwrap_or_default();
+        let parts = core
+            .split('.')
+            .map(|p| {
+                p.parse::<u8>().map_err(|_| {
+                    Error::Version(format!("Version component {p:?} is not in 0..=255"))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let [major, minor, revision] = parts[..] else {
+            return Err(Error::Version(format!(
+                "Version {s:?} is not major.minor.revision"
+            )));
+        };
+        Ok(Self {
+            major,
+            minor,
+            revision,
+            anti_rollback: 0,
+        })
+    }
+}
+
+impl fmt::Display for FwVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{} (anti_rollback={})",
+            self.major, self.minor, self.revision, self.anti_rollback
+        )
+    }
+}
+
+/// Pre-Boot Program image.
+///
+/// PBP format:
+/// [0..=3]  = b"PBP ",
+/// [4..=7]  = checksum (u32 LE),
+/// [8..end] = original binary 4-byte aligned with zero paddings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PbpImage(Vec<u8>);
+
+impl PbpImage {
+    /// Magic of a PBP.
+    pub const MAGIC: [u8; 4] = *b"PBP ";
+
+    /// Build a PBP from a linked binary.
+    ///
+    /// The binary starts with the 8-byte `.head.pbp` header of `artinchip-rt`;
+    /// its checksum field is overwritten.
+    pub fn from_binary(bin: &[u8]) -> Result<Self> {
+        if bin.len() < 8 {
+            return Err(Error::Truncated {
+                what: "PBP",
+                len: bin.len(),
+                needed: 8,
+            });
+        }
+        if !bin.starts_with(&Self::MAGIC) {
+            return Err(Error::BadMagic {
+                what: "PBP",
+                found: [bin[0], bin[1], bin[2], bin[3]],
+            });
+        }
+
+        // Align to 4 bytes, then derive the checksum from the sum with a zero checksum
+        let mut out = bin.to_vec();
+        out.resize(bin.len().next_multiple_of(4), 0);
+        out[4..8].fill(0);
+        let checksum = CHECKSUM_TARGET.wrapping_sub(word_sum(&out));
+        out[4..8].copy_from_slice(&checksum.to_le_bytes());
+        debug_assert!(verify_checksum(&out));
+        Ok(Self(out))
+    }
+
+    /// Parse a PBP, checking its magic and checksum.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 {
+            return Err(Error::Truncated {
+                what: "PBP",
+                len: data.len(),
+                needed: 8,
+            });
+        }
+        if !data.starts_with(&Self::MAGIC) {
+            return Err(Error::BadMagic {
+                what: "PBP",
+                found: [data[0], data[1], data[2], data[3]],
+            });
+        }
+        if !data.len().is_multiple_of(4) {
+            return Err(Error::BadLength {
+                what: "PBP",
+                len: data.len(),
+            });
+        }
+        if !verify_checksum(data) {
+            return Err(Error::BadChecksum { what: "PBP" });
+        }
+        Ok(Self(data.to_vec()))
+    }
+
+    /// Checksum field of the PBP header.
+    pub fn checksum(&self) -> u32 {
+        word(&self.0, 4)
+    }
+
+    /// The whole PBP, header included.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Length of the PBP in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Always false, a PBP holds at least its header.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<PbpImage> for Vec<u8> {
+    fn from(pbp: PbpImage) -> Self {
+        pbp.0
+    }
+}
+
+/// Builder of a complete AIC image around a PBP.
+///
+/// DATA1 starts at offset 256 and DATA2 follows DATA1 at the next
+/// 256-byte boundary, both sections are zero padded to 256 bytes.
+/// Unsigned images carry an MD5 digest in the SIGN area.
+#[derive(Debug, Clone)]
+pub struct ImageBuilder<'a> {
+    pbp: &'a PbpImage,
+    firmware: &'a [u8],
+    private_data: &'a [u8],
+    load_address: u32,
+    entry_point: u32,
+    fw_version: FwVersion,
+    sign_key: Option<&'a RsaPrivateKey>,
+    enc_key: Option<&'a AesKey>,
+}
+
+impl<'a> ImageBuilder<'a> {
+    /// Start an image holding `pbp` in DATA1.
+    pub fn new(pbp: &'a PbpImage) -> Self {
+        Self {
+            pbp,
+            firmware: &[],
+            private_data: &[],
+            load_address: 0,
+            entry_point: 0,
+            fw_version: FwVersion::default(),
+            sign_key: None,
+            enc_key: None,
+        }
+    }
+
+    /// Main firmware placed in DATA2, empty for a bare PBP image.
+    pub fn firmware(mut self, firmware: &'a [u8]) -> Self {
+        self.firmware = firmware;
+        self
+    }
+
+    /// Private data handed to `pbp_main`, stored in plain behind DATA2.
+    pub fn private_data(mut self, private_data: &'a [u8]) -> Self {
+        self.private_data = private_data;
+        self
+    }
+
+    /// Address the BROM loads DATA2 to.
+    pub fn load_address(mut self, load_address: u32) -> Self {
+        self.load_address = load_address;
+        self
+    }
+
+    /// Address the BROM jumps to after loading DATA2.
+    pub fn entry_point(mut self, entry_point: u32) -> Self {
+        self.entry_point = entry_point;
+        self
+    }
+
+    /// Firmware version and anti-rollback counter.
+    pub fn fw_version(mut self, fw_version: FwVersion) -> Self {
+        self.fw_version = fw_version;
+        self
+    }
+
+    /// Sign the image for secure boot instead of appending an MD5 digest.
+    pub fn sign_key(mut self, key: &'a RsaPrivateKey) -> Self {
+        self.sign_key = Some(key);
+        self
+    }
+
+    /// Encrypt DATA1 and DATA2 with AES-128-CBC.
+    pub fn encrypt(mut self, key: &'a AesKey) -> Self {
+        self.enc_key = Some(key);
+        self
+    }
+
+    /// Build the image.
+    pub fn build(&self) -> Result<Vec<u8>> {
+        // Calculate sizes
+        let pbp_len = self.pbp.len();
+        let data1_len = pbp_len.div_ceil(SECTION_ALIGN) * SECTION_ALIGN;
+        let loader_len = self.firmware.len().div_ceil(4) * 4;
+        let data2_len = loader_len.div_ceil(SECTION_ALIGN) * SECTION_ALIGN;
+        let priv_offset = HEADER_SIZE + data1_len + data2_len;
+        let priv_len = self.private_data.len();
+        let iv_offset = priv_offset + priv_len.div_ceil(4) * 4;
+        let iv_len = match self.enc_key {
+            Some(_) => crypt::AES_BLOCK_LEN,
+            None => 0,
+        };
+        let sign_offset = iv_offset + iv_len;
+        let sign_len = match self.sign_key {
+            Some(key) => sign::sign_area_len(&key.to_public_key())?,
+            None => MD5_LEN,
+        };
+        let total_len = sign_offset + sign_len;
+        if total_len > u32::MAX as usize {
+            return Err(Error::BadLength {
+                what: "image",
+                len: total_len,
+            });
+        }
+
+        let region = |offset: usize, len: usize| match len {
+            0 => Region::default(),
+            _ => Region::new(offset as u32, len as u32),
+        };
+        let head2 = Head2 {
+            header_version: Head2::VERSION,
+            image_len: total_len as u32,
+            fw_version: self.fw_version,
+            loader_len: loader_len as u32,
+            load_address: self.load_address,
+            entry_point: self.entry_point,
+            sign_algo: match self.sign_key {
+                Some(_) => sign::SIGN_ALGO_RSA2048,
+                None => sign::SIGN_ALGO_MD5,
+            },
+            enc_algo: match self.enc_key {
+                Some(_) => crypt::ENC_ALGO_AES128_CBC,
+                None => crypt::ENC_ALGO_NONE,
+            },
+            sign: Region::new(sign_offset as u32, sign_len as u32),
+            // The encryption key lives in eFuse
+            key: Region::default(),
+            iv: region(iv_offset, iv_len),
+            private_data: region(priv_offset, priv_len),
+            pbp: Region::new(HEADER_SIZE as u32, pbp_len as u32),
+        };
+
+        let mut image = vec![0u8; total_len];
+        image[0..4].copy_from_slice(&Head1::MAGIC);
+        image[HEAD1_SIZE..HEADER_SIZE].copy_from_slice(&head2.to_bytes());
+        image[HEADER_SIZE..HEADER_SIZE + pbp_len].copy_from_slice(self.pbp.as_bytes());
+        let data2_offset = HEADER_SIZE + data1_len;
+        image[data2_offset..data2_offset + self.firmware.len()].copy_from_slice(self.firmware);
+        image[priv_offset..priv_offset + priv_len].copy_from_slice(self.private_data);
+
+        // Encrypt DATA1 + DATA2 as one stream and append the IV
+        if let Some(key) = self.enc_key {
+            crypt::encrypt(key, &mut image[HEADER_SIZE..priv_offset])?;
+            image[iv_offset..sign_offset].copy_from_slice(&key.iv);
+        }
+
+        // SIGN: MD5 or RSA signature of HEAD2 + DATA1 + DATA2 + PRIV + IV
+        let signed_data = &image[HEAD1_SIZE..sign_offset];
+        let sign = match self.sign_key {
+            Some(key) => sign::sign(key, signed_data)?,
+            None => Md5::digest(signed_data).to_vec(),
+        };
+        image[sign_offset..].copy_from_slice(&sign);
+
+        // HEAD1 checksum covers the whole image
+        let checksum = CHECKSUM_TARGET.wrapping_sub(word_sum(&image));
+        image[4..8].copy_from_slice(&checksum.to_le_bytes());
+        Ok(image)
+    }
+}
+
+/// A parsed AIC image.
+///
+/// [`AicImage::parse`] checks the structure of the image; digests and
+/// signatures are checked by [`verify_image`](crate::verify::verify_image).
+#[derive(Debug, Clone, Copy)]
+pub struct AicImage<'a> {
+    pub head1: Head1,
+    pub head2: Head2,
+    data: &'a [u8],
+}
+
+impl<'a> AicImage<'a> {
+    /// Parse an image, trailing bytes behind the image length are ignored.
+    pub fn parse(data: &'a [u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE {
+            return Err(Error::Truncated {
+                what: "Image",
+                len: data.len(),
+                needed: HEADER_SIZE,
+            });
+        }
+        let head1 = Head1::parse(data)?;
+        let head2 = Head2::parse(&data[HEAD1_SIZE..])?;
+        if head1.magic != Head1::MAGIC {
+            return Err(Error::BadMagic {
+                what: "HEAD1",
+                found: head1.magic,
+            });
+        }
+        let image_len = head2.image_len as usize;
+        if image_len < HEADER_SIZE || !image_len.is_multiple_of(4) {
+            return Err(Error::BadLength {
+                what: "image",
+                len: image_len,
+            });
+        }
+        if image_len > data.len() {
+            return Err(Error::Truncated {
+                what: "Image",
+                len: data.len(),
+                needed: image_len,
+            });
+        }
+        let data = &data[..image_len];
+        if !verify_checksum(data) {
+            return Err(Error::BadChecksum { what: "Image" });
+        }
+        head2.check_regions(image_len)?;
+        Ok(Self { head1, head2, data })
+    }
+
+    /// The whole image, up to its image length.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// DATA1 PBP, still encrypted in an encrypted image.
+    pub fn pbp(&self) -> &'a [u8] {
+        self.slice(self.head2.pbp)
+    }
+
+    /// DATA2 firmware, still encrypted in an encrypted image.
+    pub fn firmware(&self) -> &'a [u8] {
+        &self.data[self.head2.firmware_range()]
+    }
+
+    /// Private data handed to the PBP.
+    pub fn private_data(&self) -> &'a [u8] {
+        self.slice(self.head2.private_data)
+    }
+
+    /// IV of an encrypted image, empty otherwise.
+    pub fn iv(&self) -> &'a [u8] {
+        self.slice(self.head2.iv)
+    }
+
+    /// MD5 digest or RSA sign result area.
+    pub fn sign_result(&self) -> &'a [u8] {
+        self.slice(self.head2.sign)
+    }
+
+    /// Data covered by the sign result: HEAD2 up to the sign result.
+    pub fn signed_data(&self) -> &'a [u8] {
+        &self.data[HEAD1_SIZE..self.head2.sign.offset as usize]
+    }
+
+    /// Whether DATA1 and DATA2 are encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        self.head2.enc_algo != crypt::ENC_ALGO_NONE
+    }
+
+    /// Copy of the image with DATA1 and DATA2 decrypted with `key` and the image IV.
+    ///
+    /// The checksum and sign result of the copy still cover the encrypted payload.
+    pub fn decrypt(&self, key: &AesKey) -> Result<Vec<u8>> {
+        decrypt_payload(self.data, &self.head2, key)
+    }
+
+    fn slice(&self, region: Region) -> &'a [u8] {
+        match region.is_empty() {
+            true => &[],
+            false => &self.data[region.offset as usize..][..region.len as usize],
+        }
+    }
+}
+
+/// Decrypt the DATA1 + DATA2 payload of an encrypted image.
+///
+/// Returns a copy of `image` with the payload in plain text.
+pub(crate) fn decrypt_payload(image: &[u8], head2: &Head2, key: &AesKey) -> Result<Vec<u8>> {
+    if head2.enc_algo != crypt::ENC_ALGO_AES128_CBC {
+        return Err(Error::Unsupported {
+            what: "enc algo",
+            value: head2.enc_algo,
+        });
+    }
+    let iv = head2.iv.range("IV", image.len())?;
+    let iv = <[u8; crypt::AES_BLOCK_LEN]>::try_from(&image[iv]).map_err(|_| Error::BadLength {
+        what: "IV",
+        len: head2.iv.len as usize,
+    })?;
+    let payload = head2.payload_range();
+    Region::new(payload.start as u32, payload.len() as u32)
+        .range("Encrypted payload", image.len())?;
+    let key = AesKey { key: key.key, iv };
+    let mut plain = image.to_vec();
+    crypt::decrypt(&key, &mut plain[payload])?;
+    Ok(plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::{QuickCheck, TestResult};
+
+    fn read_u32(buf: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn sample_pbp() -> PbpImage {
+        let mut bin = b"PBP \0\0\0\0".to_vec();
+        bin.extend((0..301u32).map(|i| (i * 7) as u8));
+        PbpImage::from_binary(&bin).unwrap()
+    }
+
+    #[test]
+    fn pbp_checksum_round_trip() {
+        let pbp = sample_pbp();
+        assert_eq!(&pbp.as_bytes()[0..4], b"PBP ");
+        assert!(pbp.len().is_multiple_of(4));
+        assert!(verify_checksum(pbp.as_bytes()));
+        assert_eq!(PbpImage::parse(pbp.as_bytes()).unwrap(), pbp);
+
+        assert!(PbpImage::from_binary(b"\x13\0\0\0\0\0\0\0").is_err());
+        let mut corrupted = pbp.as_bytes().to_vec();
+        corrupted[12] ^= 1;
+        assert!(PbpImage::parse(&corrupted).is_err());
+    }
+
+    #[test]
+    fn pack_with_data2_round_trip() {
+        let pbp = sample_pbp();
+        let firmware: Vec<u8> = (0..1001u32).map(|i| (i * 13) as u8).collect();
+        let image = ImageBuilder::new(&pbp)
+            .firmware(&firmware)
+            .load_address(0x3010_0000)
+            .entry_point(0x3010_0100)
+            .fw_version(FwVersion {
+                major: 1,
+                minor: 2,
+                revision: 3,
+                anti_rollback: 4,
+            })
+            .build()
+            .unwrap();
+
+        // HEAD1 checksum covers the whole image
+        assert_eq!(&image[0..4], b"AIC ");
+        assert!(verify_checksum(&image));
+
+        // HEAD2 fields
+        assert_eq!(read_u32(&image, 8), 0x0001_0001);
+        assert_eq!(read_u32(&image, 12) as usize, image.len());
+        assert_eq!(read_u32(&image, 16), 0x0102_0304);
+        assert_eq!(read_u32(&image, 20), 1004);
+        assert_eq!(read_u32(&image, 24), 0x3010_0000);
+        assert_eq!(read_u32(&image, 28), 0x3010_0100);
+        assert_eq!(read_u32(&image, 72), 256);
+        assert_eq!(read_u32(&image, 76) as usize, pbp.len());
+
+        // DATA1 and DATA2 placement
+        let data2_offset = 256 + pbp.len().div_ceil(256) * 256;
+        assert_eq!(&image[256..256 + pbp.len()], pbp.as_bytes());
+        assert_eq!(
+            &image[data2_offset..data2_offset + firmware.len()],
+            firmware.as_slice()
+        );
+
+        // MD5 signature over HEAD2 + DATA1 + DATA2
+        let sign_offset = read_u32(&image, 40) as usize;
+        let sign_len = read_u32(&image, 44) as usize;
+        assert_eq!(sign_offset, data2_offset + 1024);
+        assert_eq!(sign_offset + sign_len, image.len());
+        let digest = Md5::digest(&image[8..sign_offset]);
+        assert_eq!(&image[sign_offset..], digest.as_slice());
+    }
+
+    #[test]
+    fn pack_without_data2() {
+        let pbp = sample_pbp();
+        let image = ImageBuilder::new(&pbp).build().unwrap();
+        assert!(verify_checksum(&image));
+        assert_eq!(read_u32(&image, 20), 0);
+        assert_eq!(
+            read_u32(&image, 40) as usize,
+            256 + pbp.len().div_ceil(256) * 256
+        );
+    }
+
+    #[test]
+    fn pack_signed_round_trip() {
+        let pbp = sample_pbp();
+        let firmware = [0xC3u8; 512];
+        let key = sign::tests::test_key();
+        let image = ImageBuilder::new(&pbp)
+            .firmware(&firmware)
+            .sign_key(key)
+            .build()
+            .unwrap();
+        assert!(verify_checksum(&image));
+        assert_eq!(read_u32(&image, 32), sign::SIGN_ALGO_RSA2048);
+
+        let parsed = AicImage::parse(&image).unwrap();
+        assert_eq!(
+            parsed.head2.sign.offset as usize + parsed.head2.sign.len as usize,
+            image.len()
+        );
+        let public = sign::verify(parsed.sign_result(), parsed.signed_data()).unwrap();
+        assert_eq!(public, key.to_public_key());
+    }
+
+    #[test]
+    fn pack_encrypted_round_trip() {
+        let pbp = sample_pbp();
+        let firmware = [0x3Cu8; 600];
+        let key = AesKey::parse(&[0x42; 32]).unwrap();
+        let image = ImageBuilder::new(&pbp)
+            .firmware(&firmware)
+            .encrypt(&key)
+            .build()
+            .unwrap();
+        assert!(verify_checksum(&image));
+        assert_eq!(read_u32(&image, 36), crypt::ENC_ALGO_AES128_CBC);
+
+        // IV follows DATA2 and is covered by the digest
+        let iv_offset = read_u32(&image, 56) as usize;
+        let sign_offset = read_u32(&image, 40) as usize;
+        assert_eq!(read_u32(&image, 60), 16);
+        assert_eq!(iv_offset, 256 + 512 + 768);
+        assert_eq!(&image[iv_offset..iv_offset + 16], &key.iv);
+        assert_eq!(sign_offset, iv_offset + 16);
+        let digest = Md5::digest(&image[8..sign_offset]);
+        assert_eq!(&image[sign_offset..], digest.as_slice());
+
+        // DATA1 + DATA2 decrypt to the plain payload
+        let parsed = AicImage::parse(&image).unwrap();
+        assert!(parsed.is_encrypted());
+        assert_ne!(parsed.pbp(), pbp.as_bytes());
+        let plain = parsed.decrypt(&key).unwrap();
+        assert_eq!(&plain[256..256 + pbp.len()], pbp.as_bytes());
+        assert_eq!(&plain[parsed.head2.firmware_range()], firmware.as_slice());
+    }
+
+    #[test]
+    fn pack_with_private_data() {
+        let pbp = sample_pbp();
+        let private_data = crate::private_data::from_toml("uart = 1\nname = \"evb\"").unwrap();
+        let image = ImageBuilder::new(&pbp)
+            .private_data(&private_data)
+            .build()
+            .unwrap();
+        assert!(verify_checksum(&image));
+
+        let priv_offset = read_u32(&image, 64) as usize;
+        let priv_len = read_u32(&image, 68) as usize;
+        assert_eq!(priv_offset, 256 + pbp.len().div_ceil(256) * 256);
+        assert_eq!(priv_len, 8);
+        assert_eq!(
+            &image[priv_offset..priv_offset + priv_len],
+            b"\x01\0\0\0evb\0"
+        );
+        assert_eq!(read_u32(&image, 40) as usize, priv_offset + 8);
+    }
+
+    #[test]
+    fn reject_malformed_images() {
+        let pbp = sample_pbp();
+        let image = ImageBuilder::new(&pbp).firmware(&[1; 40]).build().unwrap();
+        assert!(AicImage::parse(&image).is_ok());
+        assert!(matches!(
+            AicImage::parse(&image[..100]),
+            Err(Error::Truncated { .. })
+        ));
+        assert!(matches!(
+            AicImage::parse(&image[..image.len() - 4]),
+            Err(Error::Truncated { .. })
+        ));
+
+        let mut corrupted = image.clone();
+        corrupted[0] = b'X';
+        assert!(matches!(
+            AicImage::parse(&corrupted),
+            Err(Error::BadMagic { .. })
+        ));
+
+        let mut corrupted = image.clone();
+        corrupted[300] ^= 1;
+        assert!(matches!(
+            AicImage::parse(&corrupted),
+            Err(Error::BadChecksum { .. })
+        ));
+    }
+
+    #[test]
+    fn decode_fw_version() {
+        let version = FwVersion::from_raw(0x0302_0105);
+        assert_eq!(version.major, 3);
+        assert_eq!(version.minor, 2);
+        assert_eq!(version.revision, 1);
+        assert_eq!(version.anti_rollback, 5);
+        assert_eq!(version.to_string(), "3.2.1 (anti_rollback=5)");
+        assert_eq!(version.to_raw(), 0x0302_0105);
+    }
+
+    #[test]
+    fn parse_fw_version() {
+        let version: FwVersion = "1.20.255-rc.1+build.7".parse().unwrap();
+        assert_eq!(
+            (version.major, version.minor, version.revision),
+            (1, 20, 255)
+        );
+        assert_eq!(version.anti_rollback, 0);
+        assert!("1.2".parse::<FwVersion>().is_err());
+        assert!("1.2.3.4".parse::<FwVersion>().is_err());
+        assert!("1.256.0".parse::<FwVersion>().is_err());
+    }
+
+    #[test]
+    fn fw_version_from_manifest() {
+        let dir = std::env::temp_dir().join(format!("aicfwc-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("Cargo.toml");
+        std::fs::write(
+            &manifest,
+            "[package]\nname = \"pbp\"\nversion = \"0.3.12\"\n",
+        )
+        .unwrap();
+        let version = FwVersion::from_manifest(&manifest).unwrap();
+        assert_eq!((version.major, version.minor, version.revision), (0, 3, 12));
+
+        std::fs::write(
+            &manifest,
+            "[package]\nname = \"pbp\"\nversion.workspace = true\n",
+        )
+        .unwrap();
+        assert!(FwVersion::from_manifest(&manifest).is_err());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn head2_round_trip_property() {
+        fn prop(words: Vec<u32>) -> TestResult {
+            if words.len() < 18 {
+                return TestResult::discard();
+            }
+            let mut bytes = [0u8; HEAD2_SIZE];
+            for (chunk, w) in bytes.chunks_exact_mut(4).zip(&words[..18]) {
+                chunk.copy_from_slice(&w.to_le_bytes());
+            }
+            let head2 = Head2::parse(&bytes).unwrap();
+            TestResult::from_bool(head2.to_bytes() == bytes)
+        }
+        QuickCheck::new().quickcheck(prop as fn(Vec<u32>) -> TestResult);
+    }
+
+    #[test]
+    fn image_round_trip_property() {
+        fn prop(
+            bin: Vec<u8>,
+            firmware: Vec<u8>,
+            private_data: Vec<u8>,
+            addresses: (u32, u32),
+            version: u32,
+        ) -> bool {
+            let mut bin = [b"PBP \0\0\0\0".as_slice(), &bin].concat();
+            bin.truncate(4096);
+            let pbp = PbpImage::from_binary(&bin).unwrap();
+            let fw_version = FwVersion::from_raw(version);
+            let image = ImageBuilder::new(&pbp)
+                .firmware(&firmware)
+                .private_data(&private_data)
+                .load_address(addresses.0)
+                .entry_point(addresses.1)
+                .fw_version(fw_version)
+                .build()
+                .unwrap();
+
+            let parsed = AicImage::parse(&image).unwrap();
+            let head2 = parsed.head2;
+            PbpImage::parse(parsed.pbp()).ok().as_ref() == Some(&pbp)
+                && parsed.firmware().len() == firmware.len().div_ceil(4) * 4
+                && parsed.firmware().starts_with(&firmware)
+                && parsed.private_data() == private_data.as_slice()
+                && head2.load_address == addresses.0
+                && head2.entry_point == addresses.1
+                && head2.fw_version == fw_version
+                && parsed.sign_result() == Md5::digest(parsed.signed_data()).as_slice()
+                && crate::verify::verify_image(&image, None)
+                    .unwrap()
+                    .checks
+                    .iter()
+                    .all(|c| c.result.is_ok())
+        }
+        QuickCheck::new()
+            .quickcheck(prop as fn(Vec<u8>, Vec<u8>, Vec<u8>, (u32, u32), u32) -> bool);
+    }
+}
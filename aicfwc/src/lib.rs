@@ -0,0 +1,36 @@
+//! ArtInChip firmware converter.
+//!
+//! Builds and parses the AIC boot images loaded by the ArtInChip boot ROM:
+//!
+//! ```no_run
+//! use aicfwc::{AicImage, ImageBuilder, PbpImage};
+//!
+//! # fn main() -> aicfwc::Result<()> {
+//! let bin = std::fs::read("pbp.bin").unwrap();
+//! let pbp = PbpImage::from_binary(&bin)?;
+//! let image = ImageBuilder::new(&pbp)
+//!     .load_address(0x3010_0000)
+//!     .entry_point(0x3010_0000)
+//!     .build()?;
+//!
+//! let parsed = AicImage::parse(&image)?;
+//! assert_eq!(parsed.pbp(), pbp.as_bytes());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The `aicfwc` command line tool is a thin wrapper around this crate.
+
+pub mod crypt;
+pub mod disk;
+pub mod elf;
+pub mod error;
+pub mod flash;
+pub mod image;
+pub mod private_data;
+pub mod sign;
+pub mod verify;
+
+pub use error::{Error, Result};
+pub use image::{AicImage, FwVersion, Head1, Head2, ImageBuilder, PbpImage, Region};
+pub use verify::{Check, Verification, verify_image};
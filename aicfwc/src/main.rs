@@ -1,14 +1,29 @@
+use aicfwc::crypt::AesKey;
+use aicfwc::{FwVersion, ImageBuilder, PbpImage, Verification};
+use aicfwc::{disk, elf, flash, private_data, sign, verify};
 use anyhow::{Context, Result, bail};
-use clap::{ArgAction, Parser};
-use md5::{Digest, Md5};
-use std::{fs, io::Write, path::PathBuf};
+use clap::{ArgAction, Parser, Subcommand};
+use std::{fs, io::Write, path::Path, path::PathBuf};
 
 /// AIC firmware converter.
 #[derive(Parser, Debug)]
-#[command(author, version, about)]
+#[command(
+    author,
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
-    /// The input binary file.
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The input binary or ELF file.
+    ///
+    /// ELF files are flattened from their loadable segments and must start
+    /// with the `.head.pbp` PBP header section.
+    #[arg(required = true)]
+    input: Option<PathBuf>,
 
     /// Output as PBP (Pre-Boot Program) format.
     ///
@@ -19,34 +34,258 @@ struct Cli {
     #[arg(long = "pbp", action = ArgAction::SetTrue)]
     pbp: bool,
 
+    /// Main firmware binary or ELF (application or SPL) placed in DATA2 behind the PBP.
+    #[arg(long = "firmware")]
+    firmware: Option<PathBuf>,
+
+    /// Address the BROM loads the DATA2 firmware to.
+    ///
+    /// Defaults to the load address of the firmware ELF, or of the input ELF
+    /// when no firmware is given.
+    #[arg(long = "load-addr", value_parser = parse_u32)]
+    load_addr: Option<u32>,
+
+    /// Entry point of the DATA2 firmware.
+    ///
+    /// Defaults to the ELF entry point, or to the load address for raw binaries.
+    #[arg(long = "entry", value_parser = parse_u32)]
+    entry: Option<u32>,
+
+    /// Sign the image for secure boot with this RSA-2048 private key (PEM or DER).
+    ///
+    /// Without a key the image carries an MD5 digest only.
+    #[arg(long = "sign-key")]
+    sign_key: Option<PathBuf>,
+
+    /// Encrypt DATA1 and DATA2 with AES-128-CBC using this key file.
+    ///
+    /// The file holds the 16-byte key followed by the 16-byte IV,
+    /// either as 32 raw bytes or as 64 hex digits.
+    #[arg(long = "encrypt", value_name = "KEY_FILE")]
+    encrypt: Option<PathBuf>,
+
+    /// Private data handed to `pbp_main`, a binary file or a `.toml` file.
+    ///
+    /// TOML values are serialised in order as little-endian `u32` words.
+    #[arg(long = "private-data", value_name = "FILE")]
+    private_data: Option<PathBuf>,
+
+    /// Firmware version `major.minor.revision` stamped into HEAD2.
+    #[arg(long = "fw-version", conflicts_with = "manifest")]
+    fw_version: Option<FwVersion>,
+
+    /// Take the firmware version from the package version of this `Cargo.toml`.
+    #[arg(long = "manifest", value_name = "CARGO_TOML")]
+    manifest: Option<PathBuf>,
+
+    /// Anti-rollback counter stamped into HEAD2.
+    ///
+    /// The BROM refuses images whose counter is lower than the one in eFuse.
+    #[arg(long = "anti-rollback", default_value_t = 0)]
+    anti_rollback: u8,
+
     /// Output file path.
-    #[arg(short = 'o', long = "output")]
-    output: PathBuf,
+    #[arg(short = 'o', long = "output", required = true)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Parse an AIC image, print its header and verify all checksums and digests.
+    #[command(visible_alias = "verify")]
+    Inspect {
+        /// The AIC image file (e.g. `.pk_pbp`).
+        image: PathBuf,
+
+        /// Decrypt an encrypted payload with this AES key file before checking it.
+        #[arg(long = "decrypt", value_name = "KEY_FILE")]
+        decrypt: Option<PathBuf>,
+    },
+    /// Print the public key hash (ROTPK) to program into the SID eFuse.
+    KeyHash {
+        /// RSA-2048 public or private key file (PEM or DER).
+        key: PathBuf,
+
+        /// Also write the raw hash to this file.
+        #[arg(short = 'o', long = "output")]
+        output: Option<PathBuf>,
+    },
+    /// Build a raw SD card / eMMC image with the boot image at the BROM boot sectors.
+    Sdmc {
+        /// The AIC boot image (e.g. `.pk_pbp`).
+        image: PathBuf,
+
+        /// Sector to place a copy of the boot image at, may be repeated.
+        #[arg(long = "sector", default_values_t = [disk::SDMC_BOOT_SECTOR])]
+        sectors: Vec<u64>,
+
+        /// Output disk image path.
+        #[arg(short = 'o', long = "output")]
+        output: PathBuf,
+    },
+    /// Build an SD card / USB disk image with a FAT32 boot partition.
+    Fat32 {
+        /// The AIC boot image (e.g. `.pk_pbp`).
+        image: PathBuf,
+
+        /// File name of the boot image on the FAT32 volume.
+        #[arg(long = "name", default_value = disk::DEFAULT_BOOT_FILE_NAME)]
+        name: String,
+
+        /// Disk image size in MiB.
+        #[arg(long = "size", default_value_t = 64)]
+        size: u64,
+
+        /// Output disk image path.
+        #[arg(short = 'o', long = "output")]
+        output: PathBuf,
+    },
+    /// Build a full SPI NOR / SPI NAND flash image from a partition config.
+    Flash {
+        /// Partition config file (TOML), see the `flash` module for its format.
+        config: PathBuf,
+
+        /// Output flash image path.
+        #[arg(short = 'o', long = "output")]
+        output: PathBuf,
+    },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
+    match &cli.command {
+        Some(Command::Inspect { image, decrypt }) => inspect(image, decrypt.as_deref()),
+        Some(Command::KeyHash { key, output }) => key_hash(key, output.as_deref()),
+        Some(Command::Sdmc {
+            image,
+            sectors,
+            output,
+        }) => {
+            let boot = fs::read(image).with_context(|| format!("Failed to read {image:?}"))?;
+            let disk = disk::sdmc_image(&boot, sectors)?;
+            fs::write(output, disk)
+                .with_context(|| format!("Failed to write disk image {output:?}"))
+        }
+        Some(Command::Fat32 {
+            image,
+            name,
+            size,
+            output,
+        }) => {
+            let boot = fs::read(image).with_context(|| format!("Failed to read {image:?}"))?;
+            let size = size
+                .checked_mul(1 << 20)
+                .with_context(|| format!("Disk size of {size} MiB is out of range"))?;
+            let disk = disk::fat32_image(&boot, name, size)?;
+            fs::write(output, disk)
+                .with_context(|| format!("Failed to write disk image {output:?}"))
+        }
+        Some(Command::Flash { config, output }) => {
+            let image = flash::build_from_file(config)
+                .with_context(|| format!("Failed to build flash image from {config:?}"))?;
+            fs::write(output, image)
+                .with_context(|| format!("Failed to write flash image {output:?}"))
+        }
+        None => pack(&cli),
+    }
+}
+
+/// Convert the input binary into a PBP and pack it into an AIC image.
+fn pack(cli: &Cli) -> Result<()> {
+    let input = cli.input.as_ref().context("Missing input file")?;
+    let output = cli.output.as_ref().context("Missing output file")?;
+
     // Read the input binary file
-    let bin_data = fs::read(&cli.input)
-        .with_context(|| format!("Failed to read input file {:?}", cli.input))?;
+    let mut bin_data =
+        fs::read(input).with_context(|| format!("Failed to read input file {input:?}"))?;
 
     // Currently only supports -pbp mode; error if not specified
     if !cli.pbp {
         bail!("Currently only supports -pbp preprocessing, please add -pbp flag");
     }
 
-    let pbp_bytes = build_pbp(&bin_data)?;
+    // Flatten an ELF input, it must start with the PBP header
+    let mut input_elf = None;
+    if elf::is_elf(&bin_data) {
+        let image = elf::flatten(&bin_data)
+            .with_context(|| format!("Failed to flatten input ELF {input:?}"))?;
+        elf::check_pbp_header(&bin_data, &image)
+            .with_context(|| format!("Invalid PBP ELF {input:?}"))?;
+        bin_data = image.data.clone();
+        input_elf = Some(image);
+    }
+
+    let pbp = PbpImage::from_binary(&bin_data)
+        .with_context(|| format!("Failed to build PBP from {input:?}"))?;
+
+    // Read the optional DATA2 firmware
+    let mut firmware = match &cli.firmware {
+        Some(path) => {
+            fs::read(path).with_context(|| format!("Failed to read firmware file {path:?}"))?
+        }
+        None => Vec::new(),
+    };
+    let mut firmware_elf = None;
+    if elf::is_elf(&firmware) {
+        let image = elf::flatten(&firmware)
+            .with_context(|| format!("Failed to flatten firmware ELF {:?}", cli.firmware))?;
+        firmware = image.data.clone();
+        firmware_elf = Some(image);
+    }
+
+    // Command line addresses override the ones derived from ELF files
+    let elf = if cli.firmware.is_some() {
+        firmware_elf.as_ref()
+    } else {
+        input_elf.as_ref()
+    };
+    let load_address = cli.load_addr.or(elf.map(|e| e.load_address)).unwrap_or(0);
+    let entry_point = cli
+        .entry
+        .or(elf.map(|e| e.entry_point))
+        .unwrap_or(load_address);
+    let sign_key = cli
+        .sign_key
+        .as_deref()
+        .map(sign::load_private_key)
+        .transpose()
+        .context("Failed to load sign key")?;
+    let enc_key = cli.encrypt.as_deref().map(AesKey::load).transpose()?;
+    let private_data = match &cli.private_data {
+        Some(path) => private_data::load(path)?,
+        None => Vec::new(),
+    };
+    let fw_version = match (cli.fw_version, &cli.manifest) {
+        (Some(version), _) => version,
+        (None, Some(manifest)) => FwVersion::from_manifest(manifest)?,
+        (None, None) => FwVersion::default(),
+    };
+    let mut builder = ImageBuilder::new(&pbp)
+        .firmware(&firmware)
+        .private_data(&private_data)
+        .load_address(load_address)
+        .entry_point(entry_point)
+        .fw_version(FwVersion {
+            anti_rollback: cli.anti_rollback,
+            ..fw_version
+        });
+    if let Some(key) = &sign_key {
+        builder = builder.sign_key(key);
+    }
+    if let Some(key) = &enc_key {
+        builder = builder.encrypt(key);
+    }
 
     // Write the PBP file
-    let mut f = fs::File::create(&cli.output)
-        .with_context(|| format!("Failed to create output file {:?}", cli.output))?;
-    f.write_all(&pbp_bytes)
-        .with_context(|| format!("Failed to write output file {:?}", cli.output))?;
+    let mut f = fs::File::create(output)
+        .with_context(|| format!("Failed to create output file {output:?}"))?;
+    f.write_all(pbp.as_bytes())
+        .with_context(|| format!("Failed to write output file {output:?}"))?;
 
     // Pack into full image format and write .pk_pbp file
-    let image_bytes = pack_pbp(&pbp_bytes)?;
-    let pk_pbp_path = cli.output.with_extension("pk_pbp");
+    let image_bytes = builder.build()?;
+    let pk_pbp_path = output.with_extension("pk_pbp");
     let mut f_pk = fs::File::create(&pk_pbp_path)
         .with_context(|| format!("Failed to create .pk_pbp file {pk_pbp_path:?}"))?;
     f_pk.write_all(&image_bytes)
@@ -55,137 +294,109 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-/// Build PBP format:
-/// 0..=3: "PBP "
-/// 4..=7: checksum (written back later)
-/// 8..end: bin content + zero padding to 4-byte alignment
+/// Print the header of the image at `path` and verify it.
 ///
-/// Checksum calculation:
-///   Sum all words of the final PBP file as u32 little-endian (mod 2^32),
-///   requiring the final sum == 0x0fffffff.
-///   First calculate partial_sum with checksum position as 0,
-///   then derive the checksum.
-fn build_pbp(bin: &[u8]) -> Result<Vec<u8>> {
-    // 1. Align bin to 4 bytes
-    let mut aligned = bin.to_vec();
-    while !aligned.len().is_multiple_of(4) {
-        aligned.push(0);
+/// Returns an error if any check fails.
+fn inspect(path: &Path, decrypt: Option<&Path>) -> Result<()> {
+    let image = fs::read(path).with_context(|| format!("Failed to read image file {path:?}"))?;
+    let key = decrypt.map(AesKey::load).transpose()?;
+    let Verification {
+        head1,
+        head2,
+        checks,
+        key_hash,
+    } = verify::verify_image(&image, key.as_ref())?;
+
+    println!("HEAD1:");
+    println!(
+        "  Magic:               {:?}",
+        String::from_utf8_lossy(&head1.magic)
+    );
+    println!("  Checksum:            {:#010X}", head1.checksum);
+    println!("HEAD2:");
+    println!("  Header version:      {:#010X}", head2.header_version);
+    println!("  Image length:        {}", head2.image_len);
+    println!("  Firmware version:    {}", head2.fw_version);
+    println!("  Loader length:       {}", head2.loader_len);
+    println!("  Load address:        {:#010X}", head2.load_address);
+    println!("  Entry point:         {:#010X}", head2.entry_point);
+    println!(
+        "  Sign algo:           {} ({})",
+        head2.sign_algo,
+        verify::sign_algo_name(head2.sign_algo)
+    );
+    println!(
+        "  Enc algo:            {} ({})",
+        head2.enc_algo,
+        verify::enc_algo_name(head2.enc_algo)
+    );
+    for (name, region) in [
+        ("Sign result:", head2.sign),
+        ("Encryption key:", head2.key),
+        ("IV:", head2.iv),
+        ("Private data:", head2.private_data),
+        ("PBP:", head2.pbp),
+    ] {
+        println!(
+            "  {name:<20} offset {:#X}, length {}",
+            region.offset, region.len
+        );
     }
 
-    // 2. Assemble a placeholder PBP: magic + checksum placeholder + data
-    let total_len = aligned.len();
-    let mut out = Vec::with_capacity(total_len);
-
-    // Data
-    out.extend_from_slice(&aligned);
-
-    // 3. Calculate the current 32-bit sum (with placeholder checksum=0)
-    let mut sum: u64 = 0;
-    for chunk in out.chunks_exact(4) {
-        let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-        sum = (sum + word as u64) & 0xffff_ffff;
+    if let Some(hash) = key_hash {
+        println!("  Public key hash:     {}", sign::to_hex(&hash));
     }
 
-    // 4. Derive the actual checksum
-    let target: u32 = 0xffff_ffff;
-    let checksum = target.wrapping_sub(sum as u32);
-
-    // 5. Write back checksum (little-endian)
-    out[4..8].copy_from_slice(&checksum.to_le_bytes());
-
-    // 6. Optional: Assert correctness (for debug)
-    debug_assert!(verify_checksum(&out, target));
-
-    Ok(out)
-}
+    println!("Checks:");
+    let mut failed = 0;
+    for check in &checks {
+        match &check.result {
+            Ok(()) => println!("  {:<20} OK", format!("{}:", check.name)),
+            Err(e) => {
+                failed += 1;
+                println!("  {:<20} FAILED ({e})", format!("{}:", check.name));
+            }
+        }
+    }
 
-/// Verify if the 32-bit sum of the final file is the target
-fn verify_checksum(buf: &[u8], target: u32) -> bool {
-    if !buf.len().is_multiple_of(4) {
-        return false;
+    if failed != 0 {
+        bail!("Image {path:?} is invalid: {failed} check(s) failed");
     }
-    let mut sum: u64 = 0;
-    for chunk in buf.chunks_exact(4) {
-        let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-        sum = (sum + word as u64) & 0xffff_ffff;
+    if head2.enc_algo != aicfwc::crypt::ENC_ALGO_NONE && key.is_none() {
+        println!("Payload is encrypted, pass --decrypt to check the PBP");
     }
-    (sum as u32) == target
+    println!("Image is valid");
+    Ok(())
 }
 
-/// Pack PBP data into a complete ArtInChip boot image format
-/// Format: HEAD1 + HEAD2 + DATA1 (PBP) + DATA2 (empty) + SIGN (MD5)
-fn pack_pbp(pbp_data: &[u8]) -> Result<Vec<u8>> {
-    // Constants
-    const HEAD1_SIZE: usize = 8;
-    const HEAD2_SIZE: usize = 248;
-    const SIGN_SIZE: usize = 16; // MD5
-    const ALIGNMENT: usize = 256;
-
-    // Calculate sizes
-    let pbp_total_len = pbp_data.len(); // PBP header + content
-    let data1_len = pbp_total_len.div_ceil(ALIGNMENT) * ALIGNMENT; // Align to 256 bytes
-    let data2_len = 0; // TODO: Implement DATA2
-    let signed_area_len = HEAD1_SIZE + HEAD2_SIZE + data1_len + data2_len;
-    let total_len = signed_area_len + SIGN_SIZE;
-
-    // Build HEAD1
-    let mut head1 = vec![0u8; HEAD1_SIZE];
-    head1[0..4].copy_from_slice(b"AIC "); // Magic
-    // Checksum will be calculated later
-
-    // Build HEAD2
-    let mut head2 = vec![0u8; HEAD2_SIZE];
-    // Header version: 0x00010001
-    head2[0..4].copy_from_slice(&0x00010001u32.to_le_bytes());
-    // Image length
-    head2[4..8].copy_from_slice(&(total_len as u32).to_le_bytes());
-    // Firmware version: 0.0.0 (anti_rollback=0, revision=0, minor=0, major=0)
-    // Already 0
-    // Loader length: 0
-    // Load address: 0
-    // Entry point: 0
-    // Sign algo: 0 (no signature)
-    // Enc algo: 0
-    // Sign result offset
-    head2[32..36].copy_from_slice(&(signed_area_len as u32).to_le_bytes());
-    // Sign result length: 16
-    head2[36..40].copy_from_slice(&16u32.to_le_bytes());
-    // Other offsets: 0
-    // PBP offset: 256
-    head2[64..68].copy_from_slice(&256u32.to_le_bytes());
-    // PBP length: pbp_data.len()
-    head2[68..72].copy_from_slice(&(pbp_data.len() as u32).to_le_bytes());
-    // Padding: already 0
-
-    // Build DATA1: PBP + padding
-    let mut data1 = vec![0u8; data1_len];
-    data1[0..pbp_data.len()].copy_from_slice(pbp_data);
-
-    // TODO DATA2: empty
-
-    // SIGN: MD5 of HEAD2 + DATA1 + DATA2
-    let signed_data = [head2.as_slice(), data1.as_slice()].concat();
-    let mut hasher = Md5::new();
-    hasher.update(&signed_data);
-    let sign = hasher.finalize().to_vec();
-
-    let signed_area = [head1.as_slice(), head2.as_slice(), data1.as_slice()].concat();
-
-    let mut result = vec![0u8; total_len];
-    result[0..signed_area_len].copy_from_slice(&signed_area);
-    result[signed_area_len..].copy_from_slice(&sign);
-
-    // Calculate HEAD1 checksum
-    let mut sum: u64 = 0;
-    for chunk in result.chunks_exact(4) {
-        let word = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
-        sum = (sum + word as u64) & 0xffff_ffff;
+/// Print the eFuse public key hash of `key` and optionally write it to `output`.
+fn key_hash(key: &Path, output: Option<&Path>) -> Result<()> {
+    let hash = sign::key_hash(&sign::load_public_key(key)?)?;
+    println!("{}", sign::to_hex(&hash));
+    if let Some(output) = output {
+        fs::write(output, hash)
+            .with_context(|| format!("Failed to write key hash file {output:?}"))?;
     }
+    Ok(())
+}
 
-    let target: u32 = 0xffff_ffff;
-    let head1_checksum = target.wrapping_sub(sum as u32);
+/// Parse a `u32` given in decimal or `0x`-prefixed hexadecimal.
+fn parse_u32(s: &str) -> Result<u32> {
+    let value = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(&hex.replace('_', ""), 16),
+        None => s.replace('_', "").parse(),
+    };
+    value.with_context(|| format!("Invalid 32-bit value {s:?}"))
+}
 
-    result[4..8].copy_from_slice(&head1_checksum.to_le_bytes());
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(result)
+    #[test]
+    fn parse_u32_formats() {
+        assert_eq!(parse_u32("0x3004_4000").unwrap(), 0x3004_4000);
+        assert_eq!(parse_u32("1024").unwrap(), 1024);
+        assert!(parse_u32("0xfoo").is_err());
+    }
 }
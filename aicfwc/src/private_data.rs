@@ -0,0 +1,126 @@
+//! Private data passed to the PBP.
+//!
+//! The BROM hands the private data area of the image to `pbp_main`
+//! as its `private_data` slice. It is either copied verbatim from a
+//! binary file or serialised from a TOML file.
+//!
+//! TOML values are written in document order as little-endian words,
+//! so the PBP can read them through a `#[repr(C)]` struct of `u32`s:
+//!
+//! - integers and booleans take one `u32` (negative integers as `i32`),
+//! - strings are NUL terminated and zero padded to 4 bytes,
+//! - arrays and tables are their elements in order.
+//!
+//! ```toml
+//! uart = 0           # +0x00
+//! boot_pin = 0x0507  # +0x04
+//! [psram]
+//! freq = 133000000   # +0x08
+//! timing = [3, 2, 7] # +0x0C
+//! ```
+
+use std::path::Path;
+use toml::Value;
+
+use crate::error::{self, Error, Result};
+
+/// Load private data from a binary file, or serialise it from a `.toml` file.
+pub fn load(path: &Path) -> Result<Vec<u8>> {
+    let data = error::read(path)?;
+    if path.extension().is_some_and(|ext| ext == "toml") {
+        let text = String::from_utf8(data)
+            .map_err(|_| Error::Config(format!("Private data file {path:?} is not UTF-8")))?;
+        from_toml(&text)
+            .map_err(|e| Error::Config(format!("Invalid private data file {path:?}: {e}")))
+    } else {
+        Ok(data)
+    }
+}
+
+/// Serialise a TOML document into private data.
+pub fn from_toml(text: &str) -> Result<Vec<u8>> {
+    let table: toml::Table =
+        toml::from_str(text).map_err(|e| Error::Config(format!("Failed to parse TOML: {e}")))?;
+    let mut out = Vec::new();
+    for (key, value) in &table {
+        serialize(key, value, &mut out)?;
+    }
+    Ok(out)
+}
+
+fn serialize(key: &str, value: &Value, out: &mut Vec<u8>) -> Result<()> {
+    match value {
+        Value::Integer(i) => {
+            let word = u32::try_from(*i)
+                .or_else(|_| i32::try_from(*i).map(|i| i as u32))
+                .map_err(|_| Error::Config(format!("`{key}` = {i} does not fit in 32 bits")))?;
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+        Value::Boolean(b) => out.extend_from_slice(&u32::from(*b).to_le_bytes()),
+        Value::String(s) => {
+            if s.contains('\0') {
+                return Err(Error::Config(format!("`{key}` contains a NUL character")));
+            }
+            out.extend_from_slice(s.as_bytes());
+            out.push(0);
+            out.resize(out.len().next_multiple_of(4), 0);
+        }
+        Value::Array(values) => {
+            for (i, value) in values.iter().enumerate() {
+                serialize(&format!("{key}[{i}]"), value, out)?;
+            }
+        }
+        Value::Table(table) => {
+            for (name, value) in table {
+                serialize(&format!("{key}.{name}"), value, out)?;
+            }
+        }
+        Value::Float(_) | Value::Datetime(_) => {
+            return Err(Error::Config(format!(
+                "`{key}` has an unsupported type {}",
+                value.type_str()
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_in_document_order() {
+        let data = from_toml(
+            r#"
+            uart = 2
+            offset = -1
+            enabled = true
+            [psram]
+            freq = 133_000_000
+            timing = [3, 2, 7]
+            [board]
+            name = "demo"
+            "#,
+        )
+        .unwrap();
+        let words: Vec<u32> = data
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(
+            words[..7],
+            [2, 0xFFFF_FFFF, 1, 133_000_000, 3, 2, 7],
+            "{words:x?}"
+        );
+        assert_eq!(&data[28..], b"demo\0\0\0\0");
+    }
+
+    #[test]
+    fn reject_unsupported_values() {
+        assert!(from_toml("freq = 1.5").is_err());
+        assert!(from_toml("big = 0x1_0000_0000").is_err());
+        assert!(from_toml("small = -2147483649").is_err());
+        assert!(from_toml("date = 2025-01-01").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Secure boot signing of AIC images.
+//!
+//! A signed image carries the RSA-2048 public key and a PKCS#1 v1.5
+//! SHA-256 signature in its sign result area:
+//!
+//! ```text
+//! [sign_offset..]  = public key (PKCS#1 DER), zero padded to 4 bytes
+//! [..image_len]    = signature (256 bytes)
+//! ```
+//!
+//! The signature covers the same range as the MD5 digest of unsigned images,
+//! i.e. HEAD2 + DATA1 + DATA2 + PRIV + IV. The BROM trusts the public key when its MD5
+//! hash matches the root of trust public key (ROTPK) hash burned into eFuse.
+
+use md5::{Digest, Md5};
+use rsa::pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey, EncodeRsaPublicKey};
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::traits::PublicKeyParts;
+use rsa::{Pkcs1v15Sign, RsaPrivateKey, RsaPublicKey};
+use sha2::Sha256;
+use std::path::Path;
+
+use crate::error::{self, Error, Result};
+
+/// HEAD2 sign algo: MD5 digest, no signature.
+pub const SIGN_ALGO_MD5: u32 = 0;
+/// HEAD2 sign algo: RSA-2048 PKCS#1 v1.5 with SHA-256.
+pub const SIGN_ALGO_RSA2048: u32 = 1;
+
+/// RSA-2048 signature length in bytes.
+pub const RSA2048_SIGN_LEN: usize = 256;
+
+/// Load an RSA-2048 private key in PKCS#1 or PKCS#8 format, PEM or DER encoded.
+pub fn load_private_key(path: &Path) -> Result<RsaPrivateKey> {
+    let data = error::read(path)?;
+    let key = match std::str::from_utf8(&data) {
+        Ok(pem) if pem.contains("-----BEGIN") => RsaPrivateKey::from_pkcs1_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs8_pem(pem))
+            .ok(),
+        _ => RsaPrivateKey::from_pkcs1_der(&data)
+            .or_else(|_| RsaPrivateKey::from_pkcs8_der(&data))
+            .ok(),
+    }
+    .ok_or_else(|| Error::Key(format!("{path:?} is not an RSA private key")))?;
+    check_key_size(&key.to_public_key())?;
+    Ok(key)
+}
+
+/// Load an RSA-2048 public key from a public or private key file.
+pub fn load_public_key(path: &Path) -> Result<RsaPublicKey> {
+    let data = error::read(path)?;
+    let key = match std::str::from_utf8(&data) {
+        Ok(pem) if pem.contains("PRIVATE KEY-----") => {
+            return load_private_key(path).map(|k| k.to_public_key());
+        }
+        Ok(pem) if pem.contains("-----BEGIN") => RsaPublicKey::from_pkcs1_pem(pem)
+            .or_else(|_| RsaPublicKey::from_public_key_pem(pem))
+            .ok(),
+        _ => RsaPublicKey::from_pkcs1_der(&data)
+            .or_else(|_| RsaPublicKey::from_public_key_der(&data))
+            .ok(),
+    };
+    let key = match key {
+        Some(key) => key,
+        None => load_private_key(path)
+            .map(|k| k.to_public_key())
+            .map_err(|_| Error::Key(format!("{path:?} is not an RSA key")))?,
+    };
+    check_key_size(&key)?;
+    Ok(key)
+}
+
+fn check_key_size(key: &RsaPublicKey) -> Result<()> {
+    if key.size() != RSA2048_SIGN_LEN {
+        return Err(Error::Key(format!(
+            "Only RSA-2048 keys are supported, found {} bits",
+            key.n().bits()
+        )));
+    }
+    Ok(())
+}
+
+/// PKCS#1 DER encoding of the public key as stored in the image.
+pub fn public_key_der(key: &RsaPublicKey) -> Result<Vec<u8>> {
+    Ok(key
+        .to_pkcs1_der()
+        .map_err(|e| Error::Key(format!("Failed to encode public key: {e}")))?
+        .into_vec())
+}
+
+/// MD5 hash of the public key DER, to be burned into the SID eFuse.
+pub fn key_hash(key: &RsaPublicKey) -> Result<[u8; 16]> {
+    Ok(Md5::digest(public_key_der(key)?).into())
+}
+
+/// Length of the sign result area for a key.
+pub fn sign_area_len(key: &RsaPublicKey) -> Result<usize> {
+    Ok(public_key_der(key)?.len().div_ceil(4) * 4 + RSA2048_SIGN_LEN)
+}
+
+/// Build the sign result area for `data`.
+pub fn sign(key: &RsaPrivateKey, data: &[u8]) -> Result<Vec<u8>> {
+    let mut area = public_key_der(&key.to_public_key())?;
+    area.resize(area.len().div_ceil(4) * 4, 0);
+    let signature = key
+        .sign(Pkcs1v15Sign::new::<Sha256>(), &Sha256::digest(data))
+        .map_err(|e| Error::Signature(format!("Failed to sign image: {e}")))?;
+    area.extend_from_slice(&signature);
+    Ok(area)
+}
+
+/// Verify a sign result area against `data`, returning the embedded public key.
+pub fn verify(area: &[u8], data: &[u8]) -> Result<RsaPublicKey> {
+    if area.len() < RSA2048_SIGN_LEN + 4 {
+        return Err(Error::Signature("sign result area too short".into()));
+    }
+    let (key_der, signature) = area.split_at(area.len() - RSA2048_SIGN_LEN);
+    let key = RsaPublicKey::from_pkcs1_der(&key_der[..der_len(key_der)?])
+        .map_err(|e| Error::Signature(format!("invalid embedded public key: {e}")))?;
+    check_key_size(&key)?;
+    key.verify(
+        Pkcs1v15Sign::new::<Sha256>(),
+        &Sha256::digest(data),
+        signature,
+    )
+    .map_err(|_| Error::Signature("RSA signature mismatch".into()))?;
+    Ok(key)
+}
+
+/// Length of the DER `SEQUENCE` at the start of `der`, including its header.
+fn der_len(der: &[u8]) -> Result<usize> {
+    let len = match der {
+        [0x30, n, ..] if *n < 0x80 => *n as usize + 2,
+        [0x30, 0x81, n, ..] => *n as usize + 3,
+        [0x30, 0x82, hi, lo, ..] => u16::from_be_bytes([*hi, *lo]) as usize + 4,
+        _ => {
+            return Err(Error::Signature(
+                "embedded public key is not a DER sequence".into(),
+            ));
+        }
+    };
+    if len > der.len() {
+        return Err(Error::Signature("embedded public key is truncated".into()));
+    }
+    Ok(len)
+}
+
+/// Format bytes as lowercase hex.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+    use std::sync::OnceLock;
+
+    /// Throwaway RSA-2048 key shared by all tests.
+    pub(crate) fn test_key() -> &'static RsaPrivateKey {
+        static KEY: OnceLock<RsaPrivateKey> = OnceLock::new();
+        KEY.get_or_init(|| {
+            let mut rng = StdRng::seed_from_u64(0x4149_4320);
+            RsaPrivateKey::new(&mut rng, 2048).unwrap()
+        })
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let key = test_key();
+        let data = b"HEAD2 + DATA1 + DATA2".repeat(37);
+        let area = sign(key, &data).unwrap();
+        assert_eq!(area.len(), sign_area_len(&key.to_public_key()).unwrap());
+        assert!(area.len().is_multiple_of(4));
+
+        let public = verify(&area, &data).unwrap();
+        assert_eq!(public, key.to_public_key());
+
+        let mut tampered = data.clone();
+        tampered[3] ^= 1;
+        assert!(verify(&area, &tampered).is_err());
+    }
+
+    #[test]
+    fn key_files_round_trip() {
+        use rsa::pkcs1::EncodeRsaPrivateKey;
+        use rsa::pkcs8::{EncodePublicKey, LineEnding};
+
+        let key = test_key();
+        let dir = std::env::temp_dir().join(format!("aicfwc-sign-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let private = dir.join("private.pem");
+        let public = dir.join("public.der");
+        std::fs::write(&private, key.to_pkcs1_pem(LineEnding::LF).unwrap()).unwrap();
+        std::fs::write(
+            &public,
+            key.to_public_key().to_public_key_der().unwrap().as_bytes(),
+        )
+        .unwrap();
+
+        assert_eq!(&load_private_key(&private).unwrap(), key);
+        let from_public = load_public_key(&public).unwrap();
+        let from_private = load_public_key(&private).unwrap();
+        assert_eq!(from_public, from_private);
+        assert_eq!(
+            key_hash(&from_public).unwrap(),
+            key_hash(&key.to_public_key()).unwrap()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hex_format() {
+        assert_eq!(to_hex(&[0x00, 0xAB, 0x7F]), "00ab7f");
+    }
+}
@@ -0,0 +1,353 @@
+//! AIC image verification.
+//!
+//! Unlike [`AicImage::parse`](crate::AicImage::parse), which stops at the
+//! first structural error, [`verify_image`] runs every check it can and
+//! reports each outcome, so a broken image can be diagnosed.
+
+use md5::{Digest, Md5};
+use std::borrow::Cow;
+
+use crate::crypt::{self, AesKey};
+use crate::error::{Error, Result};
+use crate::image::{
+    HEAD1_SIZE, HEADER_SIZE, Head1, Head2, MD5_LEN, PbpImage, decrypt_payload, verify_checksum,
+};
+use crate::sign;
+
+/// Outcome of a single image check.
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub result: Result<()>,
+}
+
+impl Check {
+    fn new(name: &'static str, result: Result<()>) -> Self {
+        Self { name, result }
+    }
+}
+
+/// Result of [`verify_image`].
+#[derive(Debug)]
+pub struct Verification {
+    pub head1: Head1,
+    pub head2: Head2,
+    pub checks: Vec<Check>,
+    /// eFuse hash of the embedded public key of a verified signed image.
+    pub key_hash: Option<[u8; 16]>,
+}
+
+impl Verification {
+    /// Whether every check passed.
+    pub fn is_valid(&self) -> bool {
+        self.checks.iter().all(|c| c.result.is_ok())
+    }
+}
+
+/// Human readable name of a HEAD2 sign algorithm.
+pub fn sign_algo_name(algo: u32) -> &'static str {
+    match algo {
+        sign::SIGN_ALGO_MD5 => "MD5 digest",
+        sign::SIGN_ALGO_RSA2048 => "RSA-2048 PKCS#1 v1.5 SHA-256",
+        _ => "unknown",
+    }
+}
+
+/// Human readable name of a HEAD2 encryption algorithm.
+pub fn enc_algo_name(algo: u32) -> &'static str {
+    match algo {
+        crypt::ENC_ALGO_NONE => "none",
+        crypt::ENC_ALGO_AES128_CBC => "AES-128-CBC",
+        _ => "unknown",
+    }
+}
+
+/// Parse `image` and run every check on it.
+///
+/// The PBP of an encrypted image is only checked when `key` is given.
+/// Fails only if the header cannot be parsed at all;
+/// individual check failures are reported in the returned list.
+pub fn verify_image(image: &[u8], key: Option<&AesKey>) -> Result<Verification> {
+    if image.len() < HEADER_SIZE {
+        return Err(Error::Truncated {
+            what: "Image",
+            len: image.len(),
+            needed: HEADER_SIZE,
+        });
+    }
+    let head1 = Head1::parse(image)?;
+    let head2 = Head2::parse(&image[HEAD1_SIZE..])?;
+    let mut checks = Vec::new();
+    let mut key_hash = None;
+
+    checks.push(Check::new(
+        "HEAD1 magic",
+        if head1.magic == Head1::MAGIC {
+            Ok(())
+        } else {
+            Err(Error::BadMagic {
+                what: "HEAD1",
+                found: head1.magic,
+            })
+        },
+    ));
+
+    let image_len = head2.image_len as usize;
+    let length = if image_len < HEADER_SIZE || !image_len.is_multiple_of(4) {
+        Err(Error::BadLength {
+            what: "image",
+            len: image_len,
+        })
+    } else if image_len > image.len() {
+        Err(Error::Truncated {
+            what: "Image",
+            len: image.len(),
+            needed: image_len,
+        })
+    } else {
+        Ok(())
+    };
+    let length_ok = length.is_ok();
+    checks.push(Check::new("Image length", length));
+    if !length_ok {
+        return Ok(Verification {
+            head1,
+            head2,
+            checks,
+            key_hash,
+        });
+    }
+    let image = &image[..image_len];
+
+    checks.push(Check::new(
+        "HEAD1 checksum",
+        if verify_checksum(image) {
+            Ok(())
+        } else {
+            Err(Error::BadChecksum { what: "Image" })
+        },
+    ));
+
+    checks.push(Check::new("HEAD2 offsets", head2.check_regions(image_len)));
+
+    let plain = match (head2.enc_algo, key) {
+        (crypt::ENC_ALGO_NONE, _) => Some(Cow::Borrowed(image)),
+        (crypt::ENC_ALGO_AES128_CBC, None) => None,
+        (_, Some(key)) => {
+            let result = decrypt_payload(image, &head2, key);
+            let plain = result.as_ref().ok().cloned().map(Cow::Owned);
+            checks.push(Check::new("Decryption", result.map(|_| ())));
+            plain
+        }
+        (algo, None) => {
+            checks.push(Check::new(
+                "Decryption",
+                Err(Error::Unsupported {
+                    what: "enc algo",
+                    value: algo,
+                }),
+            ));
+            None
+        }
+    };
+
+    if let (Ok(pbp), Some(plain)) = (head2.pbp.range("PBP", image_len), &plain) {
+        let pbp = &plain[pbp];
+        checks.push(Check::new(
+            "PBP magic",
+            if pbp.starts_with(&PbpImage::MAGIC) {
+                Ok(())
+            } else {
+                Err(Error::BadMagic {
+                    what: "PBP",
+                    found: pbp
+                        .get(..4)
+                        .and_then(|m| m.try_into().ok())
+                        .unwrap_or_default(),
+                })
+            },
+        ));
+        checks.push(Check::new(
+            "PBP checksum",
+            if verify_checksum(pbp) {
+                Ok(())
+            } else {
+                Err(Error::BadChecksum { what: "PBP" })
+            },
+        ));
+    }
+
+    if let Ok(sign) = head2.sign.range("Sign result", image_len) {
+        let data = &image[HEAD1_SIZE..sign.start];
+        let result = match head2.sign_algo {
+            sign::SIGN_ALGO_MD5 if sign.len() != MD5_LEN => Err(Error::Signature(format!(
+                "MD5 sign result must be 16 bytes, found {}",
+                sign.len()
+            ))),
+            sign::SIGN_ALGO_MD5 => {
+                if Md5::digest(data).as_slice() == &image[sign] {
+                    Ok(())
+                } else {
+                    Err(Error::Signature("MD5 digest mismatch".into()))
+                }
+            }
+            sign::SIGN_ALGO_RSA2048 => sign::verify(&image[sign], data).and_then(|key| {
+                key_hash = Some(sign::key_hash(&key)?);
+                Ok(())
+            }),
+            algo => Err(Error::Unsupported {
+                what: "sign algo",
+                value: algo,
+            }),
+        };
+        checks.push(Check::new("Signature", result));
+    }
+
+    Ok(Verification {
+        head1,
+        head2,
+        checks,
+        key_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::{FwVersion, ImageBuilder};
+
+    fn sample_image() -> Vec<u8> {
+        let mut bin = b"PBP \0\0\0\0".to_vec();
+        bin.extend((0..123u32).map(|i| (i * 3) as u8));
+        let pbp = PbpImage::from_binary(&bin).unwrap();
+        ImageBuilder::new(&pbp)
+            .firmware(&[0x5A; 700])
+            .load_address(0x3010_0000)
+            .entry_point(0x3010_0000)
+            .build()
+            .unwrap()
+    }
+
+    fn failed(checks: &[Check]) -> Vec<&'static str> {
+        checks
+            .iter()
+            .filter(|c| c.result.is_err())
+            .map(|c| c.name)
+            .collect()
+    }
+
+    /// Recompute the HEAD1 checksum after modifying an image.
+    fn fix_checksum(image: &mut [u8]) {
+        image[4..8].fill(0);
+        let sum = crate::image::word_sum(image);
+        image[4..8].copy_from_slice(&0xffff_ffffu32.wrapping_sub(sum).to_le_bytes());
+    }
+
+    #[test]
+    fn parse_packed_header() {
+        let image = sample_image();
+        let verification = verify_image(&image, None).unwrap();
+        assert!(verification.is_valid(), "{:?}", verification.checks);
+        let head2 = verification.head2;
+        assert_eq!(head2.image_len as usize, image.len());
+        assert_eq!(head2.loader_len, 700);
+        assert_eq!(head2.load_address, 0x3010_0000);
+        assert_eq!(head2.pbp.offset, 256);
+        assert_eq!(head2.fw_version, FwVersion::default());
+    }
+
+    #[test]
+    fn trailing_padding_is_ignored() {
+        let mut image = sample_image();
+        image.resize(image.len() + 4096, 0xFF);
+        let checks = verify_image(&image, None).unwrap().checks;
+        assert!(failed(&checks).is_empty(), "{checks:?}");
+    }
+
+    #[test]
+    fn detect_corrupted_payload() {
+        let mut image = sample_image();
+        image[600] ^= 0x01;
+        let checks = verify_image(&image, None).unwrap().checks;
+        assert_eq!(failed(&checks), ["HEAD1 checksum", "Signature"]);
+
+        fix_checksum(&mut image);
+        let checks = verify_image(&image, None).unwrap().checks;
+        assert_eq!(failed(&checks), ["Signature"]);
+    }
+
+    #[test]
+    fn detect_corrupted_pbp() {
+        let mut image = sample_image();
+        image[260] ^= 0x01;
+        fix_checksum(&mut image);
+        let checks = verify_image(&image, None).unwrap().checks;
+        assert_eq!(failed(&checks), ["PBP checksum", "Signature"]);
+    }
+
+    #[test]
+    fn detect_bad_offsets() {
+        let mut image = sample_image();
+        image[72..76].copy_from_slice(&0x10u32.to_le_bytes());
+        fix_checksum(&mut image);
+        let checks = verify_image(&image, None).unwrap().checks;
+        assert_eq!(failed(&checks), ["HEAD2 offsets", "Signature"]);
+    }
+
+    #[test]
+    fn detect_truncated_image() {
+        let image = sample_image();
+        let checks = verify_image(&image[..image.len() - 16], None)
+            .unwrap()
+            .checks;
+        assert_eq!(failed(&checks), ["Image length"]);
+        assert!(verify_image(&image[..100], None).is_err());
+    }
+
+    #[test]
+    fn verify_signed_image() {
+        let pbp = PbpImage::from_binary(b"PBP \0\0\0\0\x13\0\0\0").unwrap();
+        let key = crate::sign::tests::test_key();
+        let mut image = ImageBuilder::new(&pbp)
+            .firmware(&[0x11; 300])
+            .sign_key(key)
+            .build()
+            .unwrap();
+        let verification = verify_image(&image, None).unwrap();
+        assert!(failed(&verification.checks).is_empty());
+        assert_eq!(
+            verification.key_hash,
+            Some(sign::key_hash(&key.to_public_key()).unwrap())
+        );
+
+        image[700] ^= 0x80;
+        fix_checksum(&mut image);
+        let verification = verify_image(&image, None).unwrap();
+        assert_eq!(failed(&verification.checks), ["Signature"]);
+        assert_eq!(verification.key_hash, None);
+    }
+
+    #[test]
+    fn verify_encrypted_image() {
+        let pbp = PbpImage::from_binary(b"PBP \0\0\0\0\x13\0\0\0").unwrap();
+        let key = AesKey::parse(&[0x24; 32]).unwrap();
+        let image = ImageBuilder::new(&pbp)
+            .firmware(&[0x11; 300])
+            .encrypt(&key)
+            .build()
+            .unwrap();
+
+        // Without the key the PBP cannot be checked
+        let checks = verify_image(&image, None).unwrap().checks;
+        assert!(failed(&checks).is_empty(), "{checks:?}");
+        assert!(!checks.iter().any(|c| c.name == "PBP checksum"));
+
+        let checks = verify_image(&image, Some(&key)).unwrap().checks;
+        assert!(failed(&checks).is_empty(), "{checks:?}");
+        assert!(checks.iter().any(|c| c.name == "PBP checksum"));
+
+        let wrong = AesKey::parse(&[0x25; 32]).unwrap();
+        let checks = verify_image(&image, Some(&wrong)).unwrap().checks;
+        assert_eq!(failed(&checks), ["PBP magic", "PBP checksum"]);
+    }
+}
@@ -22,4 +22,10 @@ impl Cmu {
     pub const fn register_block(&self) -> &'static RegisterBlock {
         unsafe { &*self.reg }
     }
+
+    /// Get the PLL_INT1 output clock rate in Hz.
+    #[inline]
+    pub fn pll_int1_rate(&self) -> u32 {
+        self.register_block().pll_int1_general.read().output_rate()
+    }
 }
@@ -521,6 +521,22 @@ impl PllGeneral {
     pub const fn factor_p(self) -> u8 {
         (self.0 & Self::FACTOR_P) as u8
     }
+    /// Get pll output clock rate in Hz.
+    ///
+    /// OSC_24M when bypassed, otherwise `PLL_O` as given in [`enable_pll`](Self::enable_pll).
+    #[inline]
+    pub const fn output_rate(self) -> u32 {
+        const OSC_24M: u64 = 24_000_000;
+        match self.pll_output_sel() {
+            PllOutput::Osc24M => OSC_24M as u32,
+            PllOutput::PllClk => {
+                let n = self.factor_n() as u64 + 1;
+                let m = self.factor_m() as u64 + 1;
+                let p = self.factor_p() as u64 + 1;
+                (OSC_24M * n / (m * p)) as u32
+            }
+        }
+    }
 }
 
 /// Ldo bias current.
@@ -2360,6 +2376,13 @@ mod tests {
         val = PllGeneral(0x0).set_factor_p(0x1);
         assert_eq!(val.factor_p(), 0x1);
         assert_eq!(val.0, 0x0000_0001);
+
+        val = PllGeneral(0x0).set_factor_n(49);
+        assert_eq!(val.output_rate(), 24_000_000);
+        val = val.set_pll_output_sel(PllOutput::PllClk);
+        assert_eq!(val.output_rate(), 1_200_000_000);
+        val = val.set_factor_m(1).enable_facter_m().set_factor_p(1);
+        assert_eq!(val.output_rate(), 300_000_000);
     }
 
     test_should_panic!(
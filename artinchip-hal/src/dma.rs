@@ -1,13 +1,22 @@
 //! Direct Memory Access (DMA).
 
+pub(crate) mod cache;
 mod channel;
 mod dma_ext;
 mod instance;
+#[cfg(feature = "clic-interrupts")]
+mod non_blocking;
 mod register;
 mod task;
 
+pub use cache::CACHE_LINE;
 pub use channel::*;
+pub(crate) use channel::{
+    EVENT_ADDR_REQ_ERROR, EVENT_ALL_FINISH, EVENT_TASK_FINISH, EVENT_TASK_HALF,
+};
 pub use dma_ext::DmaExt;
 pub use instance::Dma;
+#[cfg(feature = "clic-interrupts")]
+pub use non_blocking::*;
 pub use register::*;
 pub use task::*;
@@ -0,0 +1,36 @@
+//! D-cache maintenance for DMA buffers.
+
+use core::sync::atomic::{Ordering, fence};
+use xuantie_riscv::asm::{dcache_cipa, dcache_ipa};
+
+/// D-cache line size in bytes.
+#[cfg(feature = "d21x")]
+pub const CACHE_LINE: usize = 64;
+/// D-cache line size in bytes.
+#[cfg(not(feature = "d21x"))]
+pub const CACHE_LINE: usize = 32;
+
+/// Write back and invalidate the D-cache lines covering `addr..addr + len`.
+///
+/// Call before the DMA reads memory written by the CPU.
+pub(crate) fn clean_invalidate(addr: usize, len: usize) {
+    let start = addr & !(CACHE_LINE - 1);
+    let end = (addr + len).next_multiple_of(CACHE_LINE);
+    for line in (start..end).step_by(CACHE_LINE) {
+        unsafe { dcache_cipa(line) };
+    }
+    fence(Ordering::SeqCst);
+}
+
+/// Invalidate the D-cache lines covering `addr..addr + len`.
+///
+/// Call before the CPU reads memory written by the DMA. Dirty data in
+/// partially covered lines is lost, so the range should be line aligned.
+pub(crate) fn invalidate(addr: usize, len: usize) {
+    let start = addr & !(CACHE_LINE - 1);
+    let end = (addr + len).next_multiple_of(CACHE_LINE);
+    for line in (start..end).step_by(CACHE_LINE) {
+        unsafe { dcache_ipa(line) };
+    }
+    fence(Ordering::SeqCst);
+}
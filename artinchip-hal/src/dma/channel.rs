@@ -4,6 +4,33 @@ use super::register::*;
 use super::task::DmaTask;
 use crate::cmu::Cmu;
 
+/// Task half done event bit of a channel event mask.
+pub(crate) const EVENT_TASK_HALF: u8 = 1 << 0;
+/// Task finish event bit of a channel event mask.
+pub(crate) const EVENT_TASK_FINISH: u8 = 1 << 1;
+/// All tasks finish event bit of a channel event mask.
+pub(crate) const EVENT_ALL_FINISH: u8 = 1 << 2;
+/// Address request error event bit of a channel event mask.
+pub(crate) const EVENT_ADDR_REQ_ERROR: u8 = 1 << 3;
+
+/// Pending interrupt flags of `channel` as an event mask.
+pub(crate) const fn channel_events(status: IntStatus, channel: u8) -> u8 {
+    let mut events = 0;
+    if status.is_task_half_pending(channel) {
+        events |= EVENT_TASK_HALF;
+    }
+    if status.is_task_finish_pending(channel) {
+        events |= EVENT_TASK_FINISH;
+    }
+    if status.is_all_finish_pending(channel) {
+        events |= EVENT_ALL_FINISH;
+    }
+    if status.is_addr_req_err_pending(channel) {
+        events |= EVENT_ADDR_REQ_ERROR;
+    }
+    events
+}
+
 /// DMA channels.
 pub struct DmaChannels {
     pub ch0: DmaChannel<'static, 0>,
@@ -218,7 +245,7 @@ impl<'a, const I: u8> DmaChannel<'a, I> {
         unsafe {
             self.reg
                 .int_status
-                .modify(|v| v.clear_addr_req_err_pending(I));
+                .write(IntStatus::zeroed().clear_addr_req_err_pending(I));
         }
     }
 
@@ -232,7 +259,7 @@ impl<'a, const I: u8> DmaChannel<'a, I> {
         unsafe {
             self.reg
                 .int_status
-                .modify(|v| v.clear_all_finish_pending(I));
+                .write(IntStatus::zeroed().clear_all_finish_pending(I));
         }
     }
 
@@ -246,7 +273,7 @@ impl<'a, const I: u8> DmaChannel<'a, I> {
         unsafe {
             self.reg
                 .int_status
-                .modify(|v| v.clear_task_finish_pending(I));
+                .write(IntStatus::zeroed().clear_task_finish_pending(I));
         }
     }
 
@@ -258,20 +285,63 @@ impl<'a, const I: u8> DmaChannel<'a, I> {
     /// Clear task half interrupt pending flag.
     pub fn clear_task_half_pending(&mut self) {
         unsafe {
-            self.reg.int_status.modify(|v| v.clear_task_half_pending(I));
+            self.reg
+                .int_status
+                .write(IntStatus::zeroed().clear_task_half_pending(I));
         }
     }
 
     /// Clear all interrupt pending flags for this channel.
     pub fn clear_all_pending(&mut self) {
         unsafe {
-            self.reg.int_status.modify(|v| {
-                v.clear_addr_req_err_pending(I)
+            self.reg.int_status.write(
+                IntStatus::zeroed()
+                    .clear_addr_req_err_pending(I)
                     .clear_all_finish_pending(I)
                     .clear_task_finish_pending(I)
-                    .clear_task_half_pending(I)
-            });
+                    .clear_task_half_pending(I),
+            );
+        }
+    }
+
+    /// Pending interrupt flags of this channel as an event mask.
+    pub(crate) fn pending_events(&self) -> u8 {
+        channel_events(self.reg.int_status.read(), I)
+    }
+
+    /// Clear the pending interrupt flags in the `events` mask.
+    pub(crate) fn clear_pending_events(&mut self, events: u8) {
+        let mut status = IntStatus::zeroed();
+        if events & EVENT_TASK_HALF != 0 {
+            status = status.clear_task_half_pending(I);
         }
+        if events & EVENT_TASK_FINISH != 0 {
+            status = status.clear_task_finish_pending(I);
+        }
+        if events & EVENT_ALL_FINISH != 0 {
+            status = status.clear_all_finish_pending(I);
+        }
+        if events & EVENT_ADDR_REQ_ERROR != 0 {
+            status = status.clear_addr_req_err_pending(I);
+        }
+        unsafe {
+            self.reg.int_status.write(status);
+        }
+    }
+
+    /// Events of this channel, pending or taken over by a bound `AsyncDmaHandler`.
+    pub(crate) fn events(&self) -> u8 {
+        #[cfg(feature = "clic-interrupts")]
+        return self.pending_events() | self.recorded_events();
+        #[cfg(not(feature = "clic-interrupts"))]
+        self.pending_events()
+    }
+
+    /// Clear the events in the `events` mask, pending or recorded.
+    pub(crate) fn clear_events(&mut self, events: u8) {
+        self.clear_pending_events(events);
+        #[cfg(feature = "clic-interrupts")]
+        self.clear_recorded_events(events);
     }
 
     /// Get source address.
@@ -25,6 +25,15 @@ impl Dma {
     pub const fn register_block(&self) -> &'static RegisterBlock {
         unsafe { &*self.reg }
     }
+
+    /// Get register block of the DMA controller (used by Interrupt Handler).
+    #[cfg(feature = "clic-interrupts")]
+    #[inline(always)]
+    pub(crate) unsafe fn regs() -> &'static RegisterBlock {
+        let base_addr = 0x10000000;
+
+        unsafe { &*(base_addr as *const RegisterBlock) }
+    }
 }
 
 impl DmaExt for Dma {
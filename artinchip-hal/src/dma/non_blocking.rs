@@ -0,0 +1,107 @@
+//! Async DMA channel events.
+
+use core::future::poll_fn;
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::task::Poll;
+
+use embassy_sync::waitqueue::AtomicWaker;
+
+use super::channel::{DmaChannel, channel_events};
+use super::instance::Dma;
+use crate::interrupt::clic::typelevel::{self, Interrupt as _};
+
+/// Per-channel async state: waker and the events recorded by the interrupt handler.
+pub struct ChannelState {
+    waker: AtomicWaker,
+    events: AtomicU8,
+}
+
+impl ChannelState {
+    pub const fn new() -> Self {
+        Self {
+            waker: AtomicWaker::new(),
+            events: AtomicU8::new(0),
+        }
+    }
+}
+
+impl Default for ChannelState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Global array holding the state for the 8 DMA channels.
+pub static DMA_STATES: [ChannelState; 8] = [
+    ChannelState::new(),
+    ChannelState::new(),
+    ChannelState::new(),
+    ChannelState::new(),
+    ChannelState::new(),
+    ChannelState::new(),
+    ChannelState::new(),
+    ChannelState::new(),
+];
+
+/// DMA interrupt handler for the async drivers built on [`DmaChannel`].
+///
+/// The handler takes over the pending flags of every channel, so channels
+/// must not be polled with `is_*_pending` while it is bound. The polling
+/// drivers of this crate also check the events it records.
+pub struct AsyncDmaHandler;
+
+impl typelevel::Handler<typelevel::DMA> for AsyncDmaHandler {
+    unsafe fn on_interrupt() {
+        let reg = unsafe { Dma::regs() };
+
+        // Pending flags are write-1-to-clear, writing back the value read
+        // clears exactly the flags recorded below.
+        let status = reg.int_status.read();
+        unsafe {
+            reg.int_status.write(status);
+        }
+
+        for (channel, state) in DMA_STATES.iter().enumerate() {
+            let events = channel_events(status, channel as u8);
+            if events != 0 {
+                state.events.fetch_or(events, Ordering::AcqRel);
+                state.waker.wake();
+            }
+        }
+
+        typelevel::DMA::clear_pending();
+    }
+}
+
+impl<'a, const I: u8> DmaChannel<'a, I> {
+    /// Wait until an event in the `events` mask is recorded by [`AsyncDmaHandler`].
+    ///
+    /// Returns all recorded events, which stay recorded until cleared by
+    /// [`clear_recorded_events`](Self::clear_recorded_events).
+    pub(crate) async fn wait_events(&mut self, events: u8) -> u8 {
+        let state = &DMA_STATES[I as usize];
+        poll_fn(|cx| {
+            state.waker.register(cx.waker());
+
+            let recorded = state.events.load(Ordering::Acquire);
+            if recorded & events != 0 {
+                Poll::Ready(recorded)
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    /// Events recorded by [`AsyncDmaHandler`].
+    pub(crate) fn recorded_events(&self) -> u8 {
+        DMA_STATES[I as usize].events.load(Ordering::Acquire)
+    }
+
+    /// Clear the events in the `events` mask recorded by [`AsyncDmaHandler`].
+    pub(crate) fn clear_recorded_events(&mut self, events: u8) {
+        DMA_STATES[I as usize]
+            .events
+            .fetch_and(!events, Ordering::AcqRel);
+    }
+}
@@ -341,6 +341,14 @@ impl IntStatus {
     const CH0_TASK_FINISH_IRQ_STA: u32 = 0x1 << 1;
     const CH0_TASK_HALF_IRQ_STA: u32 = 0x1;
 
+    /// Create a zero-default `IntStatus`.
+    ///
+    /// Pending flags are write-1-to-clear, so this is the base value for
+    /// clearing chosen flags without touching the others.
+    #[inline]
+    pub const fn zeroed() -> Self {
+        Self(0)
+    }
     /// Check if channel address request error interrupt is pending.
     #[inline]
     pub const fn is_addr_req_err_pending(self, channel: u8) -> bool {
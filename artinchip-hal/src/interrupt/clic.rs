@@ -1,6 +1,9 @@
 //! CLIC interrupt management.
 #![allow(unsafe_op_in_unsafe_fn)]
 
+use log::error;
+use riscv::register::mtvec;
+
 const CLIC_BASE: u32 = 0x20800000;
 
 #[inline(always)]
@@ -137,12 +140,73 @@ macro_rules! clic_interrupt_mod {
     };
 }
 
+/// Number of vector table entries, covering the interrupts of all chips.
+const VECTOR_COUNT: usize = 128;
+
+// 64-byte aligned, required by the E907 CLIC `mtvt`
+#[repr(C, align(64))]
+struct ClicVectorTable([u32; VECTOR_COUNT]);
+
+#[unsafe(link_section = ".clic.vector_table")]
+static mut VECTOR_TABLE: ClicVectorTable = ClicVectorTable([0; VECTOR_COUNT]);
+
+mod mtvt {
+    riscv::write_csr_as_usize!(0x307);
+}
+
+/// Set up CLIC vectored mode and fill the vector table.
+///
+/// Traps enter `trap_handler`. Each `(irq, handler)` of `handlers` is
+/// installed and enabled at the highest level, the other entries point to
+/// `default_handler`. Global interrupts are enabled last.
+///
+/// # Safety
+/// Call once, before the caches are enabled. `trap_handler` must be the
+/// 64-byte aligned trap entry, the handlers `riscv-interrupt-m` functions.
+pub unsafe fn init_vector_table(
+    trap_handler: usize,
+    default_handler: usize,
+    handlers: impl IntoIterator<Item = (u8, usize)>,
+) {
+    // 1. Set mtvec in CLIC mode (MODE=3)
+    mtvec::write(mtvec::Mtvec::from_bits((trap_handler & !0x3) | 3));
+
+    // 2. Initialize CLIC hardware
+    clic_init();
+
+    // 3. Fill all entries with the default handler, then the bound ones
+    let table = &raw mut VECTOR_TABLE;
+    (*table).0.fill((default_handler & !0x3) as u32);
+    for (irq, handler) in handlers {
+        (*table).0[irq as usize] = (handler & !0x3) as u32;
+        set_priority(irq, 255);
+        set_interrupt_attribute(irq, true, 0, 0);
+        enable_interrupt(irq);
+    }
+
+    // 4. Commit the table to memory and write mtvt (caches are still off)
+    core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
+    mtvt::write(table as usize);
+
+    // 5. Enable global interrupts
+    riscv::interrupt::enable();
+}
+
+/// Report an interrupt without a bound handler and halt.
+///
+/// Called by the default handler of `clic_bind_interrupts!`.
+#[doc(hidden)]
+pub fn unbound_interrupt() -> ! {
+    error!("Default interrupt handler called in CLIC vector table");
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
 #[macro_export]
 macro_rules! clic_bind_interrupts {
     ($vis:vis struct $name:ident { $($irq:ident => $handler:ty;)* }) => {
         paste::paste! {
-            use log::error;
-
             // Generate hardware vector entry for each bound interrupt
             $(
                 #[unsafe(no_mangle)]
@@ -156,64 +220,30 @@ macro_rules! clic_bind_interrupts {
             )*
 
             // Default handler (for unbound interrupts)
-            #[unsafe(no_mangle)]
-            pub extern "riscv-interrupt-m" fn __irq_handler_default() {
-                error!("Default interrupt handler called in CLIC vector table");
-                loop { core::hint::spin_loop(); }
+            extern "riscv-interrupt-m" fn [<__ $name:snake _default_handler>]() {
+                $crate::interrupt::clic::unbound_interrupt()
             }
 
-            // Global vector table, 64‑byte aligned (required by E907 CLIC mtvt hardware)
-            #[repr(C, align(64))]
-            struct ClicVectorTable([u32; 100]);
-
-            #[unsafe(link_section = ".clic.vector_table")]
-            static mut VECTOR_TABLE: ClicVectorTable = ClicVectorTable([0; 100]);
-
             #[derive(Copy, Clone)]
             $vis struct $name;
 
             impl $name {
                 /// Initialize CLIC and fill the vector table (call BEFORE cache enable).
                 pub unsafe fn init_vector_table() {
+                    unsafe extern "C" {
+                        fn AlignedTrapHandler();
+                    }
                     unsafe {
-                        // 1. Set mtvec (CLIC mode = 3, ensure address alignment)
-                        unsafe extern "C" { fn AlignedTrapHandler(); }
-                        let trap_addr = (AlignedTrapHandler as usize & !0x3) | 3;
-                        core::arch::asm!("csrw mtvec, {}", in(reg) trap_addr);
-
-                        // 2. Initialize CLIC hardware
-                        $crate::interrupt::clic::clic_init();
-
-                        // 3. Fill all entries with the default handler
-                        let default_addr = (__irq_handler_default as usize & !0x3) as u32;
-                        for entry in VECTOR_TABLE.0.iter_mut() {
-                            *entry = default_addr;
-                        }
-
-                        // 4. Fill entries for bound interrupts and configure CLIC
-                        $(
-                            let irq_num = <$crate::interrupt::clic::typelevel::$irq as $crate::interrupt::clic::typelevel::Interrupt>::IRQ;
-                            let idx = irq_num as usize;
-                            VECTOR_TABLE.0[idx] = ([<__irq_handler_ $irq>] as usize & !0x3) as u32;
-
-                            $crate::interrupt::clic::set_priority(irq_num, 255);
-                            $crate::interrupt::clic::set_interrupt_attribute(irq_num, true, 0, 0);
-                            $crate::interrupt::clic::enable_interrupt(irq_num);
-                        )*
-
-                        // 5. Ensure vector table writes are committed from store buffer to memory
-                        core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
-
-                        let vt_addr = &VECTOR_TABLE as *const _ as usize;
-
-                        // 6. Write mtvt (cache not enabled yet, direct write to memory, no flush needed)
-                        core::arch::asm!("csrw 0x307, {}", in(reg) vt_addr);
-
-                        // 7. Enable global interrupts
-                        ::riscv::interrupt::enable();
+                        $crate::interrupt::clic::init_vector_table(
+                            AlignedTrapHandler as *const () as usize,
+                            [<__ $name:snake _default_handler>] as *const () as usize,
+                            [$((
+                                <$crate::interrupt::clic::typelevel::$irq as $crate::interrupt::clic::typelevel::Interrupt>::IRQ,
+                                [<__irq_handler_ $irq>] as *const () as usize,
+                            )),*],
+                        );
                     }
                 }
-
             }
 
             // Export C ABI function for startup code (pbp.rs) to call
@@ -222,7 +252,7 @@ macro_rules! clic_bind_interrupts {
                 unsafe { $name::init_vector_table(); }
             }
 
-            // 8. Binding constraints
+            // Binding constraints
             $(
                 unsafe impl $crate::interrupt::clic::typelevel::Binding<
                     $crate::interrupt::clic::typelevel::$irq,
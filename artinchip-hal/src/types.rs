@@ -1,5 +1,7 @@
 //! Some shared types.
 
 mod ring_buffer;
+mod take_once;
 
 pub use ring_buffer::RingBuffer;
+pub use take_once::TakeOnce;
@@ -0,0 +1,53 @@
+//! Flag for handing out singletons once.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// A flag that can be taken only once, e.g. to hand out peripheral singletons.
+pub struct TakeOnce(AtomicBool);
+
+impl TakeOnce {
+    /// Create a flag that has not been taken.
+    pub const fn new() -> Self {
+        Self(AtomicBool::new(false))
+    }
+
+    /// Take the flag, returns `true` only on the first call.
+    #[inline]
+    pub fn take(&self) -> bool {
+        !self.0.swap(true, Ordering::AcqRel)
+    }
+
+    /// Check if the flag has been taken.
+    #[inline]
+    pub fn is_taken(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+}
+
+impl Default for TakeOnce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TakeOnce;
+
+    #[test]
+    fn take_once_double_take() {
+        let flag = TakeOnce::new();
+        assert!(!flag.is_taken());
+        assert!(flag.take());
+        assert!(flag.is_taken());
+        assert!(!flag.take());
+        assert!(!flag.take());
+    }
+
+    #[test]
+    fn take_once_static() {
+        static FLAG: TakeOnce = TakeOnce::new();
+        let taken = (0..4).filter(|_| FLAG.take()).count();
+        assert_eq!(taken, 1);
+    }
+}
@@ -1,8 +1,11 @@
 //! Universal Asynchronous Receiver-Transmitter (UART).
 
+mod baud;
 mod blocking;
 mod config;
+mod dma;
 mod error;
+mod flow_control;
 mod instance;
 #[cfg(feature = "uart-logger")]
 mod logger;
@@ -10,12 +13,15 @@ mod logger;
 mod non_blocking;
 mod pad;
 mod register;
+mod rs485;
 mod uart_ext;
 
+pub use baud::*;
 pub use blocking::*;
 pub use config::*;
+pub use dma::*;
 pub use error::*;
-pub use instance::Uart;
+pub use instance::{UART_COUNT, Uart};
 #[cfg(feature = "uart-logger")]
 pub use logger::*;
 #[cfg(feature = "clic-interrupts")]
@@ -0,0 +1,190 @@
+//! UART baud rate generation.
+
+use core::fmt;
+use embedded_time::rate::Baud;
+
+/// Highest module clock rate used for the UART instances.
+const MAX_MODULE_CLK_RATE: u32 = 100_000_000;
+/// Largest CMU module clock divider coefficient.
+const MAX_MODULE_CLK_DIV: u8 = 31;
+/// Largest accepted baud rate error, in parts per million.
+pub const MAX_BAUD_ERROR_PPM: u32 = 20_000;
+
+/// CMU module clock divider and UART divisor for a baud rate.
+///
+/// The module clock is `PLL_INT1 / (module_clk_div + 1)` and the baud rate
+/// is `module_clk / (16 * divisor)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BaudDivisor {
+    source_clk_rate: u32,
+    module_clk_div: u8,
+    divisor: u16,
+    requested: u32,
+}
+
+impl BaudDivisor {
+    /// Search the dividers giving the closest baud rate to `baud` from the
+    /// `source_clk_rate` of PLL_INT1, see [`Cmu::pll_int1_rate`](crate::cmu::Cmu::pll_int1_rate).
+    ///
+    /// Returns an error holding the closest dividers if the error is above
+    /// [`MAX_BAUD_ERROR_PPM`].
+    pub const fn search(source_clk_rate: u32, baud: Baud) -> Result<Self, BaudRateError> {
+        let requested = baud.0;
+        if requested == 0 {
+            return Err(BaudRateError { closest: None });
+        }
+        let mut best: Option<Self> = None;
+        let mut best_error = u32::MAX;
+        let mut module_clk_div = 0;
+        while module_clk_div <= MAX_MODULE_CLK_DIV {
+            let module_clk = source_clk_rate / (module_clk_div as u32 + 1);
+            if module_clk <= MAX_MODULE_CLK_RATE {
+                // Round to the nearest divisor.
+                let sample_rate = 16 * requested as u64;
+                let divisor = (module_clk as u64 + sample_rate / 2) / sample_rate;
+                if divisor >= 1 && divisor <= u16::MAX as u64 {
+                    let candidate = Self {
+                        source_clk_rate,
+                        module_clk_div,
+                        divisor: divisor as u16,
+                        requested,
+                    };
+                    let error = candidate.error_ppm().unsigned_abs();
+                    if error < best_error {
+                        best = Some(candidate);
+                        best_error = error;
+                    }
+                }
+            }
+            module_clk_div += 1;
+        }
+        match best {
+            Some(best) if best_error <= MAX_BAUD_ERROR_PPM => Ok(best),
+            closest => Err(BaudRateError { closest }),
+        }
+    }
+
+    /// CMU module clock divider coefficient.
+    #[inline]
+    pub const fn module_clk_div(&self) -> u8 {
+        self.module_clk_div
+    }
+
+    /// UART divisor latch value (DLL and DLH).
+    #[inline]
+    pub const fn divisor(&self) -> u16 {
+        self.divisor
+    }
+
+    /// Module clock rate in Hz.
+    #[inline]
+    pub const fn module_clk_rate(&self) -> u32 {
+        self.source_clk_rate / (self.module_clk_div as u32 + 1)
+    }
+
+    /// Baud rate that was requested.
+    #[inline]
+    pub const fn requested(&self) -> Baud {
+        Baud(self.requested)
+    }
+
+    /// Baud rate the dividers actually generate.
+    #[inline]
+    pub const fn actual(&self) -> Baud {
+        Baud(self.module_clk_rate() / (16 * self.divisor as u32))
+    }
+
+    /// Error of the actual baud rate, in parts per million of the requested one.
+    #[inline]
+    pub const fn error_ppm(&self) -> i32 {
+        let actual = self.module_clk_rate() as i64 * 1_000_000 / (16 * self.divisor as i64);
+        ((actual - self.requested as i64 * 1_000_000) / self.requested as i64) as i32
+    }
+}
+
+/// Baud rate that cannot be generated within [`MAX_BAUD_ERROR_PPM`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BaudRateError {
+    /// Closest dividers, `None` if no divisor reaches the baud rate at all.
+    pub closest: Option<BaudDivisor>,
+}
+
+impl fmt::Display for BaudRateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.closest {
+            Some(closest) => write!(
+                f,
+                "UART baud rate {} off by {} ppm, closest is {}",
+                closest.requested,
+                closest.error_ppm(),
+                closest.actual().0
+            ),
+            None => write!(f, "UART baud rate out of the divider range"),
+        }
+    }
+}
+
+impl core::error::Error for BaudRateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{BaudDivisor, BaudRateError};
+    use embedded_time::rate::Baud;
+
+    const PLL_INT1: u32 = 1_200_000_000;
+
+    #[test]
+    fn baud_divisor_exact() {
+        let div = BaudDivisor::search(PLL_INT1, Baud(3_000_000)).unwrap();
+        assert_eq!(div.actual(), Baud(3_000_000_u32));
+        assert_eq!(div.error_ppm(), 0);
+        let div = BaudDivisor::search(PLL_INT1, Baud(1_500_000)).unwrap();
+        assert_eq!(div.actual(), Baud(1_500_000_u32));
+        assert_eq!(div.error_ppm(), 0);
+    }
+
+    #[test]
+    fn baud_divisor_common_rates() {
+        for baud in [
+            9600_u32, 19200, 38400, 57600, 115200, 230400, 460800, 921600,
+        ] {
+            let div = BaudDivisor::search(PLL_INT1, Baud(baud)).unwrap();
+            assert!(div.module_clk_rate() <= 100_000_000);
+            assert_eq!(
+                div.module_clk_rate() / (16 * div.divisor() as u32),
+                div.actual().0
+            );
+            assert!(div.error_ppm().unsigned_abs() < 5_000, "{baud}: {div:?}");
+        }
+        // The former fixed 48 MHz / 16 / 3 gave 1 Mbaud (+8.5%) for 921600.
+        let div = BaudDivisor::search(PLL_INT1, Baud(921600)).unwrap();
+        assert_eq!(div.module_clk_div(), 26);
+        assert_eq!(div.divisor(), 3);
+        assert_eq!(div.error_ppm(), 4_693);
+    }
+
+    #[test]
+    fn baud_divisor_out_of_range() {
+        let none = Err(BaudRateError { closest: None });
+        assert_eq!(BaudDivisor::search(PLL_INT1, Baud(0)), none);
+        assert_eq!(BaudDivisor::search(PLL_INT1, Baud(10)), none);
+        // 100 MHz / 16 = 6.25 Mbaud at most
+        let closest = BaudDivisor::search(PLL_INT1, Baud(10_000_000))
+            .unwrap_err()
+            .closest
+            .unwrap();
+        assert_eq!(closest.actual(), Baud(6_250_000_u32));
+        assert_eq!(closest.error_ppm(), -375_000);
+    }
+
+    #[test]
+    fn baud_divisor_source_clock() {
+        // 24 MHz with PLL_INT1 bypassed to OSC_24M
+        let div = BaudDivisor::search(24_000_000, Baud(115200)).unwrap();
+        assert_eq!(div.module_clk_div(), 0);
+        assert_eq!(div.divisor(), 13);
+        assert_eq!(div.module_clk_rate(), 24_000_000);
+        assert!(div.error_ppm().unsigned_abs() < 2_000);
+        assert!(BaudDivisor::search(24_000_000, Baud(3_000_000)).is_err());
+    }
+}
@@ -1,10 +1,13 @@
 //! Blocking serial communication interface.
 
+use super::baud::{BaudDivisor, BaudRateError};
 use super::config::{StopBits, UartConfig};
 use super::error::UartError;
+use super::flow_control;
 use super::instance::Uart;
-use super::pad::{Receive, Transmit, UartPad};
+use super::pad::{Cts, Receive, Rts, Transmit, UartPad};
 use super::register::RegisterBlock;
+use super::rs485;
 use crate::cmu::Cmu;
 use uart16550::TriggerLevel;
 
@@ -17,6 +20,8 @@ where
     reg: &'a RegisterBlock,
     tx: TX,
     rx: RX,
+    baud: BaudDivisor,
+    rx_error: Option<UartError>,
 }
 
 impl<'a, const I: u8, TX, RX> BlockingSerial<'a, I, TX, RX>
@@ -25,11 +30,43 @@ where
     RX: UartPad<I> + Receive<I>,
 {
     /// Create a new blocking serial.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM), see [`try_new`](Self::try_new).
     pub fn new(reg: &'a RegisterBlock, tx: TX, rx: RX, config: UartConfig, cmu: &mut Cmu) -> Self {
+        match Self::try_new(reg, tx, rx, config, cmu) {
+            Ok(serial) => serial,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Create a new blocking serial, or return the closest baud rate if the
+    /// requested one cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM).
+    pub fn try_new(
+        reg: &'a RegisterBlock,
+        tx: TX,
+        rx: RX,
+        config: UartConfig,
+        cmu: &mut Cmu,
+    ) -> Result<Self, BaudRateError> {
+        Self::init(reg, tx, rx, config, false, cmu)
+    }
+
+    fn init(
+        reg: &'a RegisterBlock,
+        tx: TX,
+        rx: RX,
+        config: UartConfig,
+        flow_control: bool,
+        cmu: &mut Cmu,
+    ) -> Result<Self, BaudRateError> {
+        // Pick the module clock and UART divisors for the baud rate from PLL_INT1
+        let baud = BaudDivisor::search(cmu.pll_int1_rate(), config.baud_rate)?;
+
         // Enable clocks for the UART instance
-        // TODO: flexible clock frequency handling
-        let fix_mod_clk_rate = 48_000_000;
-        let fix_mod_div = 24;
         let clk = cmu.register_block();
         let uart_clk = match I {
             0 => &clk.clock_uart0,
@@ -45,7 +82,10 @@ where
         unsafe {
             // Initialize module clock.
             // Reference: https://aicdoc.artinchip.com/topics/ic/cmu/cmu-function2-d13x.html#topic_yvp_f24_4bc__table_qb3_bn5_ydc
-            uart_clk.modify(|v| v.set_module_clk_div(fix_mod_div).enable_module_clk());
+            uart_clk.modify(|v| {
+                v.set_module_clk_div(baud.module_clk_div())
+                    .enable_module_clk()
+            });
             uart_clk.modify(|v| v.enable_bus_clk());
             uart_clk.modify(|v| v.enable_module_reset());
             riscv::asm::delay(500);
@@ -53,7 +93,6 @@ where
         }
 
         // Parse configuration
-        let baud_rate = config.baud_rate.0;
         let data_bits = config.data_bits;
         let stop_bits = config.stop_bits;
         let parity = config.parity;
@@ -75,8 +114,7 @@ where
         );
 
         // Write baud rate divisor
-        let uart_divisor = fix_mod_clk_rate / (16 * baud_rate);
-        uart16550.write_divisor(uart_divisor as u16);
+        uart16550.write_divisor(baud.divisor());
 
         // Update HALT register to apply configuration
         unsafe {
@@ -91,6 +129,16 @@ where
                 .set_parity(parity.to_parity()),
         );
 
+        // Configure RS-485 half-duplex mode
+        if let Some(rs485) = &config.rs485 {
+            rs485::configure(reg, rs485);
+        }
+
+        // Enable auto flow control on the RTS and CTS pads
+        if flow_control {
+            flow_control::enable(reg);
+        }
+
         // Enable FIFO and set trigger levels.
         uart16550.iir_fcr().write(TriggerLevel::_14.and_reset());
 
@@ -100,32 +148,42 @@ where
             reg.rx_ctl.modify(|v| v.enable_rx());
         }
 
-        Self { reg, tx, rx }
+        Ok(Self {
+            reg,
+            tx,
+            rx,
+            baud,
+            rx_error: None,
+        })
+    }
+
+    /// Baud rate dividers, with the actual baud rate and its error.
+    #[inline]
+    pub fn baud(&self) -> BaudDivisor {
+        self.baud
     }
 
     /// Blocking write buffer.
     pub fn blocking_write(&mut self, buf: &[u8]) -> Result<usize, UartError> {
-        let uart16550 = &self.reg.uart16550;
-
-        for &b in buf {
-            let mut timeout = 100_000;
-            while !uart16550.lsr().read().is_transmitter_fifo_empty() {
-                timeout -= 1;
-                if timeout == 0 {
-                    return Err(UartError::Timeout);
-                }
-                core::hint::spin_loop();
-            }
-            uart16550.rbr_thr().tx_data(b);
-        }
-        Ok(buf.len())
+        write_fifo(self.reg, buf)
     }
 
-    /// Blocking read buffer.
+    /// Blocking read buffer, waits for at least one byte.
+    ///
+    /// Returns the bytes received so far; a line error is returned on the
+    /// next call if some bytes were read before it.
     pub fn blocking_read(&mut self, buf: &mut [u8]) -> Result<usize, UartError> {
-        // TODO: implement reading into buffer
-        buf.fill(0);
-        Ok(0)
+        read_fifo(self.reg, &mut self.rx_error, buf, None)
+    }
+
+    /// Blocking read buffer, waits for at least one byte up to `timeout`
+    /// status polls.
+    pub fn blocking_read_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: u32,
+    ) -> Result<usize, UartError> {
+        read_fifo(self.reg, &mut self.rx_error, buf, Some(timeout))
     }
 
     /// Statically split into transmit and receive halves.
@@ -136,8 +194,9 @@ where
                 _pad: self.tx,
             },
             ReceiveHalf {
-                _reg: self.reg,
+                reg: self.reg,
                 _pad: self.rx,
+                rx_error: self.rx_error,
             },
         )
     }
@@ -166,13 +225,61 @@ where
     }
 }
 
+impl<'a, const I: u8, TX, RX, RTS, CTS> BlockingSerial<'a, I, (TX, CTS), (RX, RTS)>
+where
+    TX: UartPad<I> + Transmit<I>,
+    RX: UartPad<I> + Receive<I>,
+    RTS: UartPad<I> + Rts<I>,
+    CTS: UartPad<I> + Cts<I>,
+{
+    /// Create a new blocking serial with RTS/CTS pads.
+    ///
+    /// The RTS and CTS pads are paired with the RX and TX pads of the serial.
+    /// 16550 auto RTS and auto CTS flow control is enabled on them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM), see
+    /// [`try_new_with_flow_control`](Self::try_new_with_flow_control).
+    pub fn new_with_flow_control(
+        reg: &'a RegisterBlock,
+        tx: TX,
+        rx: RX,
+        rts: RTS,
+        cts: CTS,
+        config: UartConfig,
+        cmu: &mut Cmu,
+    ) -> Self {
+        match Self::try_new_with_flow_control(reg, tx, rx, rts, cts, config, cmu) {
+            Ok(serial) => serial,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Create a new blocking serial with RTS/CTS pads, or return the closest
+    /// baud rate if the requested one cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM).
+    pub fn try_new_with_flow_control(
+        reg: &'a RegisterBlock,
+        tx: TX,
+        rx: RX,
+        rts: RTS,
+        cts: CTS,
+        config: UartConfig,
+        cmu: &mut Cmu,
+    ) -> Result<Self, BaudRateError> {
+        Self::init(reg, (tx, cts), (rx, rts), config, true, cmu)
+    }
+}
+
 /// Transmit half of the serial interface.
 pub struct TransmitHalf<'a, const I: u8, TX>
 where
     TX: UartPad<I> + Transmit<I>,
 {
-    reg: &'a RegisterBlock,
-    _pad: TX,
+    pub(super) reg: &'a RegisterBlock,
+    pub(super) _pad: TX,
 }
 
 impl<'a, const I: u8, TX> TransmitHalf<'a, I, TX>
@@ -181,20 +288,7 @@ where
 {
     /// Blocking write buffer.
     pub fn blocking_write(&mut self, buf: &[u8]) -> Result<usize, UartError> {
-        let uart16550 = &self.reg.uart16550;
-
-        for &b in buf {
-            let mut timeout = 100_000;
-            while !uart16550.lsr().read().is_transmitter_fifo_empty() {
-                timeout -= 1;
-                if timeout == 0 {
-                    return Err(UartError::Timeout);
-                }
-                core::hint::spin_loop();
-            }
-            uart16550.rbr_thr().tx_data(b);
-        }
-        Ok(buf.len())
+        write_fifo(self.reg, buf)
     }
 }
 
@@ -203,20 +297,105 @@ pub struct ReceiveHalf<'a, const I: u8, RX>
 where
     RX: UartPad<I> + Receive<I>,
 {
-    _reg: &'a RegisterBlock,
-    _pad: RX,
+    pub(super) reg: &'a RegisterBlock,
+    pub(super) _pad: RX,
+    pub(super) rx_error: Option<UartError>,
 }
 
 impl<'a, const I: u8, RX> ReceiveHalf<'a, I, RX>
 where
     RX: UartPad<I> + Receive<I>,
 {
-    /// Blocking read buffer.
+    /// Blocking read buffer, waits for at least one byte.
+    ///
+    /// Returns the bytes received so far; a line error is returned on the
+    /// next call if some bytes were read before it.
     pub fn blocking_read(&mut self, buf: &mut [u8]) -> Result<usize, UartError> {
-        // TODO: implement reading into buffer
-        buf.fill(0);
-        Ok(0)
+        read_fifo(self.reg, &mut self.rx_error, buf, None)
+    }
+
+    /// Blocking read buffer, waits for at least one byte up to `timeout`
+    /// status polls.
+    pub fn blocking_read_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: u32,
+    ) -> Result<usize, UartError> {
+        read_fifo(self.reg, &mut self.rx_error, buf, Some(timeout))
+    }
+}
+
+/// Write `buf` into the transmit FIFO.
+///
+/// Polls `USR` rather than `LSR`, as reading `LSR` clears the receive errors.
+fn write_fifo(reg: &RegisterBlock, buf: &[u8]) -> Result<usize, UartError> {
+    for &b in buf {
+        let mut timeout = 100_000;
+        while !reg.usr.read().is_transmit_fifo_not_full() {
+            timeout -= 1;
+            if timeout == 0 {
+                return Err(UartError::Timeout);
+            }
+            core::hint::spin_loop();
+        }
+        reg.uart16550.rbr_thr().tx_data(b);
+    }
+    Ok(buf.len())
+}
+
+/// Read the receive FIFO into `buf`, waiting for the first byte up to
+/// `timeout` status polls, or forever if `None`.
+///
+/// A line error after some bytes is kept in `rx_error` for the next read.
+fn read_fifo(
+    reg: &RegisterBlock,
+    rx_error: &mut Option<UartError>,
+    buf: &mut [u8],
+    mut timeout: Option<u32>,
+) -> Result<usize, UartError> {
+    if buf.is_empty() {
+        return Ok(0);
+    }
+    if let Some(error) = rx_error.take() {
+        return Err(error);
+    }
+    let uart16550 = &reg.uart16550;
+    let mut len = 0;
+    while len < buf.len() {
+        // Reading LSR clears its error bits, so read it once per byte.
+        let lsr = uart16550.lsr().read();
+        let error = UartError::from_line_status(lsr);
+        if lsr.is_data_ready() {
+            let byte = uart16550.rbr_thr().rx_data();
+            // Break, framing and parity errors belong to this byte, drop it.
+            if !matches!(
+                error,
+                Some(UartError::Break | UartError::Framing | UartError::Parity)
+            ) {
+                buf[len] = byte;
+                len += 1;
+            }
+        }
+        if let Some(error) = error {
+            if len == 0 {
+                return Err(error);
+            }
+            *rx_error = Some(error);
+            return Ok(len);
+        }
+        if !lsr.is_data_ready() {
+            if len > 0 {
+                break;
+            }
+            match &mut timeout {
+                Some(0) => return Err(UartError::Timeout),
+                Some(t) => *t -= 1,
+                None => {}
+            }
+            core::hint::spin_loop();
+        }
     }
+    Ok(len)
 }
 
 impl<'a, const I: u8, TX, RX> embedded_io::ErrorType for BlockingSerial<'a, I, TX, RX>
@@ -306,3 +485,45 @@ where
         self.blocking_read(buf)
     }
 }
+
+impl<'a, const I: u8, TX, RX> embedded_io::ReadReady for BlockingSerial<'a, I, TX, RX>
+where
+    TX: UartPad<I> + Transmit<I>,
+    RX: UartPad<I> + Receive<I>,
+{
+    #[inline]
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.rx_error.is_some() || self.reg.usr.read().is_receive_fifo_not_empty())
+    }
+}
+
+impl<'a, const I: u8, RX> embedded_io::ReadReady for ReceiveHalf<'a, I, RX>
+where
+    RX: UartPad<I> + Receive<I>,
+{
+    #[inline]
+    fn read_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.rx_error.is_some() || self.reg.usr.read().is_receive_fifo_not_empty())
+    }
+}
+
+impl<'a, const I: u8, TX, RX> embedded_io::WriteReady for BlockingSerial<'a, I, TX, RX>
+where
+    TX: UartPad<I> + Transmit<I>,
+    RX: UartPad<I> + Receive<I>,
+{
+    #[inline]
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.reg.usr.read().is_transmit_fifo_not_full())
+    }
+}
+
+impl<'a, const I: u8, TX> embedded_io::WriteReady for TransmitHalf<'a, I, TX>
+where
+    TX: UartPad<I> + Transmit<I>,
+{
+    #[inline]
+    fn write_ready(&mut self) -> Result<bool, Self::Error> {
+        Ok(self.reg.usr.read().is_transmit_fifo_not_full())
+    }
+}
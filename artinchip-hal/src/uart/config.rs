@@ -48,11 +48,42 @@ impl Parity {
     }
 }
 
+/// RS-485 half-duplex configuration.
+///
+/// The DE (driver enable) pin is driven by the hardware around each frame.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Rs485Config {
+    /// Time from DE assert to the start bit, in serial clock cycles (0..=15).
+    pub de_assert_time: u8,
+    /// Time from the stop bit to DE de-assert, in serial clock cycles (0..=15).
+    pub de_deassert_time: u8,
+    /// Address to match in 9-bit multidrop receive, `None` to receive all bytes.
+    ///
+    /// Only the bytes after a matching address byte are received; the parity
+    /// bit carries the 9th bit, so [`UartConfig::parity`] is ignored.
+    pub address: Option<u8>,
+    /// Wait for an idle bus before transmitting.
+    pub bus_idle_check: bool,
+}
+
+impl Default for Rs485Config {
+    fn default() -> Self {
+        Self {
+            de_assert_time: 1,
+            de_deassert_time: 1,
+            address: None,
+            bus_idle_check: true,
+        }
+    }
+}
+
 pub struct UartConfig {
     pub baud_rate: Baud,
     pub data_bits: DataBits,
     pub stop_bits: StopBits,
     pub parity: Parity,
+    /// RS-485 half-duplex mode, `None` for full-duplex.
+    pub rs485: Option<Rs485Config>,
 }
 
 impl Default for UartConfig {
@@ -62,6 +93,7 @@ impl Default for UartConfig {
             data_bits: DataBits::Eight,
             stop_bits: StopBits::One,
             parity: Parity::None,
+            rs485: None,
         }
     }
 }
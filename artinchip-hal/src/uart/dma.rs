@@ -0,0 +1,588 @@
+//! DMA transmit and receive.
+//!
+//! The UART raises DMA requests through the `UART_HSK` handshake, and a
+//! [`DmaChannel`] moves the data between memory and the UART FIFOs. Transfers
+//! are polled in [`Blocking`] mode, or awaited in [`Async`] mode with the
+//! `AsyncDmaHandler` bound to the `DMA` interrupt.
+
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use critical_section::Mutex;
+
+use super::blocking::{ReceiveHalf, TransmitHalf};
+use super::error::UartError;
+use super::pad::{Receive, Transmit, UartPad};
+use super::register::{DmaHandshakeConfig, DmaHandshakeMode, RegisterBlock};
+use crate::dma::{
+    BurstSize, CACHE_LINE, ChConfig, ChMode, DataWidth, DmaChannel, DmaTask, EVENT_ADDR_REQ_ERROR,
+    EVENT_ALL_FINISH, EVENT_TASK_FINISH, EVENT_TASK_HALF, HandshakeMode, cache,
+};
+#[cfg(feature = "clic-interrupts")]
+use crate::{dma::AsyncDmaHandler, interrupt::clic::typelevel};
+
+/// DMA port of the SRAM.
+const MEM_DMA_PORT: u8 = 0;
+/// DMA port of UART0, UART`n` is on port `UART0_DMA_PORT + n`.
+const UART0_DMA_PORT: u8 = 14;
+
+/// Transfers are polled for completion.
+pub struct Blocking;
+
+/// Transfers are awaited, woken by the `AsyncDmaHandler`.
+pub struct Async;
+
+/// Transmit direction in [`HandshakeUse::directions`].
+const DMA_TX: u8 = 1 << 0;
+/// Receive direction in [`HandshakeUse::directions`].
+const DMA_RX: u8 = 1 << 1;
+
+/// DMA directions of a UART in use and its `UART_HSK` from before the first.
+#[derive(Clone, Copy)]
+struct HandshakeUse {
+    directions: u8,
+    saved: DmaHandshakeConfig,
+}
+
+/// Handshake users of each UART, `UART_HSK` is shared by both directions.
+static HANDSHAKE_USE: Mutex<Cell<[Option<HandshakeUse>; 8]>> = Mutex::new(Cell::new([None; 8]));
+
+/// Switch UART `I` to DMA handshake mode for `direction`.
+fn enable_handshake<const I: u8>(reg: &RegisterBlock, direction: u8) {
+    critical_section::with(|cs| {
+        let cell = HANDSHAKE_USE.borrow(cs);
+        let mut uses = cell.get();
+        let entry = uses[I as usize].get_or_insert(HandshakeUse {
+            directions: 0,
+            saved: reg.hsk.read(),
+        });
+        entry.directions |= direction;
+        cell.set(uses);
+        unsafe {
+            reg.hsk
+                .modify(|v| v.set_handshake_mode(DmaHandshakeMode::Handshake));
+        }
+    });
+}
+
+/// Release the DMA handshake of UART `I` for `direction`, restoring
+/// `UART_HSK` once no direction uses it.
+fn disable_handshake<const I: u8>(reg: &RegisterBlock, direction: u8) {
+    critical_section::with(|cs| {
+        let cell = HANDSHAKE_USE.borrow(cs);
+        let mut uses = cell.get();
+        if let Some(entry) = &mut uses[I as usize] {
+            entry.directions &= !direction;
+            if entry.directions == 0 {
+                unsafe { reg.hsk.write(entry.saved) };
+                uses[I as usize] = None;
+            }
+        }
+        cell.set(uses);
+    });
+}
+
+/// Let the UART raise DMA requests for its transmit FIFO.
+///
+/// Returns the previous `dma_pte_tx`, for [`disable_tx_dma`].
+fn enable_tx_dma<const I: u8>(reg: &RegisterBlock) -> bool {
+    enable_handshake::<I>(reg, DMA_TX);
+    let pte = reg.halt.read().is_dma_pte_tx_set();
+    // Request data while the transmit FIFO is below its trigger level.
+    unsafe { reg.halt.modify(|v| v.set_dma_pte_tx(true)) };
+    pte
+}
+
+/// Stop the DMA requests of the transmit FIFO, restoring `dma_pte_tx` to `pte`.
+fn disable_tx_dma<const I: u8>(reg: &RegisterBlock, pte: bool) {
+    unsafe { reg.halt.modify(|v| v.set_dma_pte_tx(pte)) };
+    disable_handshake::<I>(reg, DMA_TX);
+}
+
+/// Let the UART raise DMA requests for its receive FIFO.
+///
+/// Returns the previous `dma_pte_rx`, for [`disable_rx_dma`].
+fn enable_rx_dma<const I: u8>(reg: &RegisterBlock) -> bool {
+    enable_handshake::<I>(reg, DMA_RX);
+    let pte = reg.halt.read().is_dma_pte_rx_set();
+    // Request every received byte rather than only above the trigger
+    // level, so that the tail of a message is not held in the FIFO.
+    unsafe { reg.halt.modify(|v| v.set_dma_pte_rx(false)) };
+    pte
+}
+
+/// Stop the DMA requests of the receive FIFO, restoring `dma_pte_rx` to `pte`.
+fn disable_rx_dma<const I: u8>(reg: &RegisterBlock, pte: bool) {
+    unsafe { reg.halt.modify(|v| v.set_dma_pte_rx(pte)) };
+    disable_handshake::<I>(reg, DMA_RX);
+}
+
+/// Task moving `len` bytes from `src` in memory into the transmit FIFO.
+fn tx_task<const I: u8>(reg: &RegisterBlock, src: u32, len: u32) -> DmaTask {
+    DmaTask {
+        cfg: ChConfig::zeroed()
+            .set_src_dev(MEM_DMA_PORT)
+            .set_src_data_width(DataWidth::Bits8)
+            .set_src_burst(BurstSize::Burst1)
+            .enable_src_addr_inc()
+            .set_snk_dev(UART0_DMA_PORT + I)
+            .set_snk_data_width(DataWidth::Bits8)
+            .set_snk_burst(BurstSize::Burst1)
+            .disable_snk_addr_inc(),
+        src,
+        // THR is the first register of the block.
+        dst: reg as *const _ as u32,
+        len,
+        delay: DmaTask::DEFAULT_DELAY,
+        p_next: DmaTask::TASK_END,
+        mode: ChMode::zeroed()
+            .set_src_mode(HandshakeMode::Wait)
+            .set_snk_mode(HandshakeMode::Handshake),
+        v_next: None,
+    }
+}
+
+/// Task moving `len` bytes from the receive FIFO to `dst` in memory.
+fn rx_task<const I: u8>(reg: &RegisterBlock, dst: u32, len: u32) -> DmaTask {
+    DmaTask {
+        cfg: ChConfig::zeroed()
+            .set_src_dev(UART0_DMA_PORT + I)
+            .set_src_data_width(DataWidth::Bits8)
+            .set_src_burst(BurstSize::Burst1)
+            .disable_src_addr_inc()
+            .set_snk_dev(MEM_DMA_PORT)
+            .set_snk_data_width(DataWidth::Bits8)
+            .set_snk_burst(BurstSize::Burst1)
+            .enable_snk_addr_inc(),
+        // RBR is the first register of the block.
+        src: reg as *const _ as u32,
+        dst,
+        len,
+        delay: DmaTask::DEFAULT_DELAY,
+        p_next: DmaTask::TASK_END,
+        mode: ChMode::zeroed()
+            .set_src_mode(HandshakeMode::Handshake)
+            .set_snk_mode(HandshakeMode::Wait),
+        v_next: None,
+    }
+}
+
+/// Transmit half of the serial interface, writing through a DMA channel.
+pub struct DmaTransmitHalf<'a, const I: u8, const C: u8, TX, M>
+where
+    TX: UartPad<I> + Transmit<I>,
+{
+    reg: &'a RegisterBlock,
+    pad: TX,
+    ch: DmaChannel<'a, C>,
+    task: DmaTask,
+    /// `dma_pte_tx` to restore on [`free`](Self::free).
+    pte: bool,
+    _mode: PhantomData<M>,
+}
+
+impl<'a, const I: u8, TX> TransmitHalf<'a, I, TX>
+where
+    TX: UartPad<I> + Transmit<I>,
+{
+    /// Write through DMA channel `ch`, polling for completion.
+    pub fn with_dma<const C: u8>(
+        self,
+        ch: DmaChannel<'a, C>,
+    ) -> DmaTransmitHalf<'a, I, C, TX, Blocking> {
+        DmaTransmitHalf::new(self, ch)
+    }
+
+    /// Write through DMA channel `ch`, awaiting completion.
+    #[cfg(feature = "clic-interrupts")]
+    pub fn with_dma_async<const C: u8, IRQS>(
+        self,
+        ch: DmaChannel<'a, C>,
+        _irqs: IRQS,
+    ) -> DmaTransmitHalf<'a, I, C, TX, Async>
+    where
+        IRQS: typelevel::Binding<typelevel::DMA, AsyncDmaHandler>,
+    {
+        DmaTransmitHalf::new(self, ch)
+    }
+}
+
+impl<'a, const I: u8, const C: u8, TX, M> DmaTransmitHalf<'a, I, C, TX, M>
+where
+    TX: UartPad<I> + Transmit<I>,
+{
+    fn new(tx: TransmitHalf<'a, I, TX>, ch: DmaChannel<'a, C>) -> Self {
+        let pte = enable_tx_dma::<I>(tx.reg);
+        Self {
+            reg: tx.reg,
+            pad: tx._pad,
+            ch,
+            task: tx_task::<I>(tx.reg, 0, 0),
+            pte,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Start the transfer of `buf`, which must stay untouched until it completes.
+    fn start(&mut self, buf: &[u8]) {
+        self.task = tx_task::<I>(self.reg, buf.as_ptr() as u32, buf.len() as u32);
+
+        // The DMA reads the buffer and the task from memory.
+        cache::clean_invalidate(buf.as_ptr() as usize, buf.len());
+        cache::clean_invalidate(
+            &self.task as *const _ as usize,
+            core::mem::size_of::<DmaTask>(),
+        );
+
+        self.ch.start(&self.task);
+    }
+
+    /// Release the transmit half and the DMA channel.
+    ///
+    /// The UART stops raising transmit DMA requests.
+    pub fn free(self) -> (TransmitHalf<'a, I, TX>, DmaChannel<'a, C>) {
+        disable_tx_dma::<I>(self.reg, self.pte);
+        let tx = TransmitHalf {
+            reg: self.reg,
+            _pad: self.pad,
+        };
+        (tx, self.ch)
+    }
+}
+
+impl<'a, const I: u8, const C: u8, TX> DmaTransmitHalf<'a, I, C, TX, Blocking>
+where
+    TX: UartPad<I> + Transmit<I>,
+{
+    /// Blocking write buffer, returns once the DMA moved all of it into the FIFO.
+    ///
+    /// Also completes with the `AsyncDmaHandler` bound for other channels.
+    pub fn blocking_write(&mut self, buf: &[u8]) -> Result<usize, UartError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let events = EVENT_ALL_FINISH | EVENT_ADDR_REQ_ERROR;
+        self.ch.clear_events(events);
+        self.start(buf);
+
+        // A bound `AsyncDmaHandler` takes the flags over for any channel.
+        let events = loop {
+            let events = self.ch.events() & events;
+            if events != 0 {
+                break events;
+            }
+            core::hint::spin_loop();
+        };
+        self.ch.clear_events(events);
+
+        if events & EVENT_ADDR_REQ_ERROR != 0 {
+            self.ch.stop();
+            return Err(UartError::Dma);
+        }
+        Ok(buf.len())
+    }
+}
+
+#[cfg(feature = "clic-interrupts")]
+impl<'a, const I: u8, const C: u8, TX> DmaTransmitHalf<'a, I, C, TX, Async>
+where
+    TX: UartPad<I> + Transmit<I>,
+{
+    /// Write buffer, completes once the DMA moved all of it into the FIFO.
+    ///
+    /// Dropping the future stops the transfer.
+    pub async fn write(&mut self, buf: &[u8]) -> Result<usize, UartError> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let events = EVENT_ALL_FINISH | EVENT_ADDR_REQ_ERROR;
+        self.ch.clear_recorded_events(events);
+        self.start(buf);
+
+        let guard = StopOnDrop(&mut self.ch);
+        let recorded = guard.0.wait_events(events).await;
+        core::mem::forget(guard);
+        self.ch.clear_recorded_events(events);
+
+        if recorded & EVENT_ADDR_REQ_ERROR != 0 {
+            self.ch.stop();
+            return Err(UartError::Dma);
+        }
+        Ok(buf.len())
+    }
+}
+
+/// Stops the DMA channel of a cancelled transfer.
+#[cfg(feature = "clic-interrupts")]
+struct StopOnDrop<'c, 'a, const C: u8>(&'c mut DmaChannel<'a, C>);
+
+#[cfg(feature = "clic-interrupts")]
+impl<'c, 'a, const C: u8> Drop for StopOnDrop<'c, 'a, C> {
+    fn drop(&mut self) {
+        self.0.stop();
+    }
+}
+
+/// Half of the receive ring buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RingHalf {
+    First,
+    Second,
+}
+
+impl RingHalf {
+    /// Channel event raised once the DMA has filled this half.
+    const fn filled_event(self) -> u8 {
+        match self {
+            Self::First => EVENT_TASK_HALF,
+            Self::Second => EVENT_TASK_FINISH,
+        }
+    }
+
+    const fn other(self) -> Self {
+        match self {
+            Self::First => Self::Second,
+            Self::Second => Self::First,
+        }
+    }
+}
+
+/// Check the channel `events` for the awaited ring `half`.
+///
+/// Returns `None` while the half is being filled. The event of the other
+/// half being pending as well means the DMA has wrapped around into the
+/// awaited half and is overwriting it.
+const fn check_half(events: u8, half: RingHalf) -> Option<Result<(), UartError>> {
+    if events & EVENT_ADDR_REQ_ERROR != 0 {
+        Some(Err(UartError::Dma))
+    } else if events & half.filled_event() == 0 {
+        None
+    } else if events & half.other().filled_event() != 0 {
+        Some(Err(UartError::Overrun))
+    } else {
+        Some(Ok(()))
+    }
+}
+
+/// Receive half of the serial interface, filling a ring buffer through a
+/// DMA channel.
+///
+/// The DMA runs continuously, raising an event each time it has filled a
+/// half of the buffer. Each half must be read before the DMA fills the
+/// other one, or the read reports [`UartError::Overrun`].
+pub struct DmaReceiveRing<'a, const I: u8, const C: u8, RX, M>
+where
+    RX: UartPad<I> + Receive<I>,
+{
+    reg: &'a RegisterBlock,
+    pad: RX,
+    ch: DmaChannel<'a, C>,
+    buf: &'static mut [u8],
+    half: RingHalf,
+    /// `dma_pte_rx` to restore on [`free`](Self::free).
+    pte: bool,
+    _mode: PhantomData<M>,
+}
+
+impl<'a, const I: u8, RX> ReceiveHalf<'a, I, RX>
+where
+    RX: UartPad<I> + Receive<I>,
+{
+    /// Receive into ring buffer `buf` through DMA channel `ch`, polling for
+    /// filled halves.
+    ///
+    /// `task` holds the looping DMA task while the ring runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is not aligned to [`CACHE_LINE`], or if its length is
+    /// not a non-zero multiple of two cache lines.
+    pub fn into_dma_ring<const C: u8>(
+        self,
+        ch: DmaChannel<'a, C>,
+        buf: &'static mut [u8],
+        task: &'static mut DmaTask,
+    ) -> DmaReceiveRing<'a, I, C, RX, Blocking> {
+        DmaReceiveRing::new(self, ch, buf, task)
+    }
+
+    /// Receive into ring buffer `buf` through DMA channel `ch`, awaiting
+    /// filled halves.
+    ///
+    /// `task` holds the looping DMA task while the ring runs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `buf` is not aligned to [`CACHE_LINE`], or if its length is
+    /// not a non-zero multiple of two cache lines.
+    #[cfg(feature = "clic-interrupts")]
+    pub fn into_dma_ring_async<const C: u8, IRQS>(
+        self,
+        ch: DmaChannel<'a, C>,
+        buf: &'static mut [u8],
+        task: &'static mut DmaTask,
+        _irqs: IRQS,
+    ) -> DmaReceiveRing<'a, I, C, RX, Async>
+    where
+        IRQS: typelevel::Binding<typelevel::DMA, AsyncDmaHandler>,
+    {
+        DmaReceiveRing::new(self, ch, buf, task)
+    }
+}
+
+impl<'a, const I: u8, const C: u8, RX, M> DmaReceiveRing<'a, I, C, RX, M>
+where
+    RX: UartPad<I> + Receive<I>,
+{
+    fn new(
+        rx: ReceiveHalf<'a, I, RX>,
+        mut ch: DmaChannel<'a, C>,
+        buf: &'static mut [u8],
+        task: &'static mut DmaTask,
+    ) -> Self {
+        // Each half is invalidated on its own, without touching other data.
+        assert!(
+            (buf.as_ptr() as usize).is_multiple_of(CACHE_LINE),
+            "UART DMA ring buffer must be aligned to a cache line"
+        );
+        assert!(
+            !buf.is_empty() && buf.len().is_multiple_of(2 * CACHE_LINE),
+            "UART DMA ring buffer length must be a multiple of two cache lines"
+        );
+
+        *task = rx_task::<I>(rx.reg, buf.as_ptr() as u32, buf.len() as u32);
+        // Loop the task onto itself; `v_next` stays empty as the chain is
+        // never walked in software.
+        task.p_next = &*task as *const _ as u32;
+
+        let pte = enable_rx_dma::<I>(rx.reg);
+        ch.clear_events(EVENT_TASK_HALF | EVENT_TASK_FINISH | EVENT_ADDR_REQ_ERROR);
+        cache::invalidate(buf.as_ptr() as usize, buf.len());
+        cache::clean_invalidate(&*task as *const _ as usize, core::mem::size_of::<DmaTask>());
+        ch.start(task);
+
+        Self {
+            reg: rx.reg,
+            pad: rx._pad,
+            ch,
+            buf,
+            half: RingHalf::First,
+            pte,
+            _mode: PhantomData,
+        }
+    }
+
+    /// Advance the ring on the channel `events`.
+    ///
+    /// Returns the events handled, to be cleared, and the bytes of the
+    /// filled half; `None` while the awaited half is being filled.
+    fn advance(&mut self, events: u8) -> Option<(u8, Result<Range<usize>, UartError>)> {
+        if let Err(err) = check_half(events, self.half)? {
+            // On overrun the DMA is in the awaited half again, so keep
+            // waiting on that half.
+            let handled = events & (EVENT_TASK_HALF | EVENT_TASK_FINISH | EVENT_ADDR_REQ_ERROR);
+            return Some((handled, Err(err)));
+        }
+
+        let half_len = self.buf.len() / 2;
+        let range = match self.half {
+            RingHalf::First => 0..half_len,
+            RingHalf::Second => half_len..self.buf.len(),
+        };
+        let handled = self.half.filled_event();
+        self.half = self.half.other();
+        Some((handled, Ok(range)))
+    }
+
+    /// Bytes of the filled half in `range`, fresh from memory.
+    fn filled(&self, range: Range<usize>) -> &[u8] {
+        let bytes = &self.buf[range];
+        cache::invalidate(bytes.as_ptr() as usize, bytes.len());
+        bytes
+    }
+
+    /// Stop receiving, and release the receive half and the DMA channel.
+    ///
+    /// Bytes received after the last filled half are lost.
+    pub fn free(mut self) -> (ReceiveHalf<'a, I, RX>, DmaChannel<'a, C>) {
+        self.ch.stop();
+        disable_rx_dma::<I>(self.reg, self.pte);
+        let rx = ReceiveHalf {
+            reg: self.reg,
+            _pad: self.pad,
+            rx_error: None,
+        };
+        (rx, self.ch)
+    }
+}
+
+impl<'a, const I: u8, const C: u8, RX> DmaReceiveRing<'a, I, C, RX, Blocking>
+where
+    RX: UartPad<I> + Receive<I>,
+{
+    /// Blocking read of the next filled half of the ring buffer.
+    pub fn blocking_read_half(&mut self) -> Result<&[u8], UartError> {
+        loop {
+            if let Some((handled, result)) = self.advance(self.ch.events()) {
+                self.ch.clear_events(handled);
+                return result.map(|range| self.filled(range));
+            }
+            core::hint::spin_loop();
+        }
+    }
+}
+
+#[cfg(feature = "clic-interrupts")]
+impl<'a, const I: u8, const C: u8, RX> DmaReceiveRing<'a, I, C, RX, Async>
+where
+    RX: UartPad<I> + Receive<I>,
+{
+    /// Read the next filled half of the ring buffer.
+    pub async fn read_half(&mut self) -> Result<&[u8], UartError> {
+        let events = self
+            .ch
+            .wait_events(self.half.filled_event() | EVENT_ADDR_REQ_ERROR)
+            .await;
+        let Some((handled, result)) = self.advance(events) else {
+            unreachable!("awaited ring half event not recorded");
+        };
+        self.ch.clear_recorded_events(handled);
+        result.map(|range| self.filled(range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RingHalf, UartError, check_half};
+    use crate::dma::{EVENT_ADDR_REQ_ERROR, EVENT_TASK_FINISH, EVENT_TASK_HALF};
+
+    #[test]
+    fn ring_half_filled() {
+        assert_eq!(check_half(0, RingHalf::First), None);
+        assert_eq!(check_half(EVENT_TASK_FINISH, RingHalf::First), None);
+        assert_eq!(check_half(EVENT_TASK_HALF, RingHalf::First), Some(Ok(())));
+        assert_eq!(check_half(EVENT_TASK_HALF, RingHalf::Second), None);
+        assert_eq!(
+            check_half(EVENT_TASK_FINISH, RingHalf::Second),
+            Some(Ok(()))
+        );
+    }
+
+    #[test]
+    fn ring_half_overrun() {
+        let both = EVENT_TASK_HALF | EVENT_TASK_FINISH;
+        assert_eq!(
+            check_half(both, RingHalf::First),
+            Some(Err(UartError::Overrun))
+        );
+        assert_eq!(
+            check_half(both, RingHalf::Second),
+            Some(Err(UartError::Overrun))
+        );
+        assert_eq!(
+            check_half(EVENT_ADDR_REQ_ERROR, RingHalf::First),
+            Some(Err(UartError::Dma))
+        );
+    }
+}
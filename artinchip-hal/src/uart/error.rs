@@ -1,18 +1,52 @@
 //! UART error types.
 
 use core::fmt;
+use uart16550::LineStatus;
 
 /// UART bus error.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UartError {
     /// Timeout waiting for hardware.
     Timeout,
+    /// Receive FIFO overrun, bytes after the last read one were lost.
+    Overrun,
+    /// Parity error on a received byte.
+    Parity,
+    /// Framing error (invalid stop bit) on a received byte.
+    Framing,
+    /// Break condition, the line was held low for longer than a frame.
+    Break,
+    /// DMA address request error on the UART transfer.
+    Dma,
+}
+
+impl UartError {
+    /// Receive error flagged in the line status, if any.
+    pub(crate) const fn from_line_status(lsr: LineStatus) -> Option<Self> {
+        // A break also sets the framing error bit.
+        if lsr.is_break_condition() {
+            Some(Self::Break)
+        } else if lsr.is_framing_error() {
+            Some(Self::Framing)
+        } else if lsr.is_parity_error() {
+            Some(Self::Parity)
+        } else if lsr.is_overrun_error() {
+            Some(Self::Overrun)
+        } else {
+            None
+        }
+    }
 }
 
 impl fmt::Display for UartError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Timeout => write!(f, "UART timeout"),
+            Self::Overrun => write!(f, "UART receive overrun"),
+            Self::Parity => write!(f, "UART parity error"),
+            Self::Framing => write!(f, "UART framing error"),
+            Self::Break => write!(f, "UART break condition"),
+            Self::Dma => write!(f, "UART DMA address request error"),
         }
     }
 }
@@ -21,6 +55,28 @@ impl core::error::Error for UartError {}
 
 impl embedded_io::Error for UartError {
     fn kind(&self) -> embedded_io::ErrorKind {
-        embedded_io::ErrorKind::Other
+        match self {
+            Self::Timeout => embedded_io::ErrorKind::TimedOut,
+            Self::Overrun | Self::Dma => embedded_io::ErrorKind::Other,
+            // Not `Interrupted`, which generic read loops retry.
+            Self::Parity | Self::Framing | Self::Break => embedded_io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UartError;
+    use embedded_io::{Error, ErrorKind};
+
+    #[test]
+    fn uart_error_kind() {
+        assert_eq!(UartError::Timeout.kind(), ErrorKind::TimedOut);
+        assert_eq!(UartError::Overrun.kind(), ErrorKind::Other);
+        assert_eq!(UartError::Parity.kind(), ErrorKind::InvalidData);
+        assert_eq!(UartError::Framing.kind(), ErrorKind::InvalidData);
+        // A break must not be retried as `Interrupted` by read loops.
+        assert_eq!(UartError::Break.kind(), ErrorKind::InvalidData);
+        assert_eq!(UartError::Dma.kind(), ErrorKind::Other);
     }
 }
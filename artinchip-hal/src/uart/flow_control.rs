@@ -0,0 +1,20 @@
+//! Hardware RTS/CTS flow control.
+
+use super::register::RegisterBlock;
+use uart16550::ModemControl;
+
+/// Auto flow control enable bit of `MCR` (`AFCE`).
+const MCR_AFCE: u8 = 0x1 << 5;
+/// Request to send bit of `MCR` (`RTS`), enables auto RTS with `AFCE`.
+const MCR_RTS: u8 = 0x1 << 1;
+
+/// Enable 16550 auto RTS and auto CTS.
+///
+/// RTS is de-asserted when the receive FIFO reaches its trigger level, and
+/// the transmitter holds while CTS is de-asserted.
+pub(crate) fn enable(reg: &RegisterBlock) {
+    let mcr = reg.uart16550.mcr().read();
+    reg.uart16550
+        .mcr()
+        .write(ModemControl(mcr.0 | MCR_AFCE | MCR_RTS));
+}
@@ -2,7 +2,7 @@
 
 use super::blocking::BlockingSerial;
 use super::config::UartConfig;
-use super::pad::{Receive, Transmit, UartPad};
+use super::pad::{Cts, Receive, Rts, Transmit, UartPad};
 use super::register::RegisterBlock;
 use super::uart_ext::UartExt;
 use crate::cmu::Cmu;
@@ -45,7 +45,25 @@ impl_uart_interrupts! {
     (7, UART7),
 }
 
+/// Number of UART instances on the chip.
+#[cfg(feature = "d12x")]
+pub const UART_COUNT: u8 = 4;
+/// Number of UART instances on the chip.
+#[cfg(not(feature = "d12x"))]
+pub const UART_COUNT: u8 = 8;
+
 /// UART with statically known instance number.
+///
+/// Instances beyond [`UART_COUNT`] fail to build, e.g. UART5 on the D12x,
+/// which only has UART0 to UART3:
+///
+#[cfg_attr(feature = "d12x", doc = "```compile_fail")]
+#[cfg_attr(not(feature = "d12x"), doc = "```no_run")]
+/// use artinchip_hal::uart::{Uart, UART_COUNT};
+///
+/// let uart5 = Uart::<5>::__new(0x1871_5000 as *const _);
+/// # let _ = (uart5, UART_COUNT);
+/// ```
 pub struct Uart<const I: u8> {
     reg: *const RegisterBlock,
     _private: PhantomData<()>,
@@ -54,6 +72,7 @@ pub struct Uart<const I: u8> {
 impl<const I: u8> Uart<I> {
     /// Create a new UART instance.
     pub const fn __new(reg: *const RegisterBlock) -> Self {
+        const { assert!(I < UART_COUNT, "UART instance does not exist on this chip") };
         Self {
             reg,
             _private: PhantomData,
@@ -90,6 +109,24 @@ impl<const I: u8> UartExt<'static, I> for Uart<I> {
     {
         BlockingSerial::new(self.register_block(), tx, rx, config, cmu)
     }
+    #[inline]
+    fn new_blocking_with_flow_control<TX, RX, RTS, CTS>(
+        self,
+        tx: TX,
+        rx: RX,
+        rts: RTS,
+        cts: CTS,
+        config: UartConfig,
+        cmu: &mut Cmu,
+    ) -> BlockingSerial<'static, I, (TX, CTS), (RX, RTS)>
+    where
+        TX: UartPad<I> + Transmit<I>,
+        RX: UartPad<I> + Receive<I>,
+        RTS: UartPad<I> + Rts<I>,
+        CTS: UartPad<I> + Cts<I>,
+    {
+        BlockingSerial::new_with_flow_control(self.register_block(), tx, rx, rts, cts, config, cmu)
+    }
     #[cfg(feature = "clic-interrupts")]
     #[inline]
     fn new_async<TX, RX, IRQS>(
@@ -114,4 +151,32 @@ impl<const I: u8> UartExt<'static, I> for Uart<I> {
     {
         AsyncSerial::new(self.register_block(), tx, rx, config, cmu)
     }
+    #[cfg(feature = "clic-interrupts")]
+    #[inline]
+    fn new_async_with_flow_control<TX, RX, RTS, CTS, IRQS>(
+        self,
+        tx: TX,
+        rx: RX,
+        rts: RTS,
+        cts: CTS,
+        config: UartConfig,
+        cmu: &mut Cmu,
+        _irqs: IRQS,
+    ) -> AsyncSerial<'static, I, (TX, CTS), (RX, RTS)>
+    where
+        Self: Sized + UartInterrupt<I>,
+        TX: UartPad<I> + Transmit<I>,
+        RX: UartPad<I> + Receive<I>,
+        RTS: UartPad<I> + Rts<I>,
+        CTS: UartPad<I> + Cts<I>,
+        Uart<I>: UartInterrupt<I>,
+        AsyncUartHandler<I>:
+            crate::interrupt::clic::typelevel::Handler<<Uart<I> as UartInterrupt<I>>::Interrupt>,
+        IRQS: crate::interrupt::clic::typelevel::Binding<
+                <Uart<I> as UartInterrupt<I>>::Interrupt,
+                AsyncUartHandler<I>,
+            >,
+    {
+        AsyncSerial::new_with_flow_control(self.register_block(), tx, rx, rts, cts, config, cmu)
+    }
 }
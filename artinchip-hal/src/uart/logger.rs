@@ -62,6 +62,12 @@ impl Log for Uart0Logger {
 /// The returned `BlockingSerial` can be used for subsequent blocking I/O
 /// independently of the logger (theoretically it won't conflict with logging,
 /// but using UART0 for general data transfer is not recommended).
+///
+/// # Panics
+///
+/// Panics if the baud rate cannot be generated within
+/// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM), or if flow control
+/// is enabled.
 pub fn uart_logger_init<TX, RX>(
     uart: Uart<0>,
     tx: TX,
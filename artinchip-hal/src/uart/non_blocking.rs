@@ -8,10 +8,13 @@ use critical_section::Mutex;
 use embassy_sync::waitqueue::AtomicWaker;
 use uart16550::{PendingInterrupt, TriggerLevel};
 
+use super::baud::{BaudDivisor, BaudRateError};
 use super::config::{StopBits, UartConfig};
+use super::flow_control;
 use super::instance::{Uart, UartInterrupt};
-use super::pad::{Receive, Transmit, UartPad};
+use super::pad::{Cts, Receive, Rts, Transmit, UartPad};
 use super::register::RegisterBlock;
+use super::rs485;
 use crate::cmu::Cmu;
 use crate::interrupt::clic::typelevel::{self, Interrupt as _};
 use crate::types::RingBuffer;
@@ -169,6 +172,7 @@ where
     pub reg: &'a RegisterBlock,
     _tx: TX,
     _rx: RX,
+    baud: BaudDivisor,
 }
 
 impl<'a, const I: u8, TX, RX> AsyncSerial<'a, I, TX, RX>
@@ -177,10 +181,44 @@ where
     RX: UartPad<I> + Receive<I>,
     Uart<I>: UartInterrupt<I>,
 {
+    /// Create a new non-blocking serial.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM), see [`try_new`](Self::try_new).
     pub fn new(reg: &'a RegisterBlock, tx: TX, rx: RX, config: UartConfig, cmu: &mut Cmu) -> Self {
+        match Self::try_new(reg, tx, rx, config, cmu) {
+            Ok(serial) => serial,
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    /// Create a new non-blocking serial, or return the closest baud rate if the
+    /// requested one cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM).
+    pub fn try_new(
+        reg: &'a RegisterBlock,
+        tx: TX,
+        rx: RX,
+        config: UartConfig,
+        cmu: &mut Cmu,
+    ) -> Result<Self, BaudRateError> {
+        Self::init(reg, tx, rx, config, false, cmu)
+    }
+
+    fn init(
+        reg: &'a RegisterBlock,
+        tx: TX,
+        rx: RX,
+        config: UartConfig,
+        flow_control: bool,
+        cmu: &mut Cmu,
+    ) -> Result<Self, BaudRateError> {
+        // Pick the module clock and UART divisors for the baud rate from PLL_INT1
+        let baud = BaudDivisor::search(cmu.pll_int1_rate(), config.baud_rate)?;
+
         // Enable clocks for the UART instance
-        let fix_mod_clk_rate = 48_000_000;
-        let fix_mod_div = 24;
         let clk = cmu.register_block();
         let uart_clk = match I {
             0 => &clk.clock_uart0,
@@ -195,7 +233,10 @@ where
         };
         unsafe {
             // Initialize module clock
-            uart_clk.modify(|v| v.set_module_clk_div(fix_mod_div).enable_module_clk());
+            uart_clk.modify(|v| {
+                v.set_module_clk_div(baud.module_clk_div())
+                    .enable_module_clk()
+            });
             uart_clk.modify(|v| v.enable_bus_clk());
             uart_clk.modify(|v| v.enable_module_reset());
             riscv::asm::delay(500);
@@ -203,7 +244,6 @@ where
         }
 
         // Parse configuration
-        let baud_rate = config.baud_rate.0;
         let data_bits = config.data_bits;
         let stop_bits = config.stop_bits;
         let parity = config.parity;
@@ -225,8 +265,7 @@ where
         );
 
         // Write baud rate divisor
-        let uart_divisor = fix_mod_clk_rate / (16 * baud_rate);
-        uart16550.write_divisor(uart_divisor as u16);
+        uart16550.write_divisor(baud.divisor());
 
         // Update HALT register to apply configuration
         unsafe {
@@ -241,6 +280,16 @@ where
                 .set_parity(parity.to_parity()),
         );
 
+        // Configure RS-485 half-duplex mode
+        if let Some(rs485) = &config.rs485 {
+            rs485::configure(reg, rs485);
+        }
+
+        // Enable auto flow control on the RTS and CTS pads
+        if flow_control {
+            flow_control::enable(reg);
+        }
+
         // Enable FIFO and set trigger levels.
         uart16550.iir_fcr().write(TriggerLevel::_14.and_reset());
 
@@ -256,12 +305,68 @@ where
             .ier()
             .write(uart16550.ier().read().enable_rda().enable_rls());
 
-        Self {
+        Ok(Self {
             reg,
             _tx: tx,
             _rx: rx,
+            baud,
+        })
+    }
+
+    /// Baud rate dividers, with the actual baud rate and its error.
+    #[inline]
+    pub fn baud(&self) -> BaudDivisor {
+        self.baud
+    }
+}
+
+impl<'a, const I: u8, TX, RX, RTS, CTS> AsyncSerial<'a, I, (TX, CTS), (RX, RTS)>
+where
+    TX: UartPad<I> + Transmit<I>,
+    RX: UartPad<I> + Receive<I>,
+    RTS: UartPad<I> + Rts<I>,
+    CTS: UartPad<I> + Cts<I>,
+    Uart<I>: UartInterrupt<I>,
+{
+    /// Create a new non-blocking serial with RTS/CTS pads.
+    ///
+    /// The RTS and CTS pads are paired with the RX and TX pads of the serial.
+    /// 16550 auto RTS and auto CTS flow control is enabled on them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM), see
+    /// [`try_new_with_flow_control`](Self::try_new_with_flow_control).
+    pub fn new_with_flow_control(
+        reg: &'a RegisterBlock,
+        tx: TX,
+        rx: RX,
+        rts: RTS,
+        cts: CTS,
+        config: UartConfig,
+        cmu: &mut Cmu,
+    ) -> Self {
+        match Self::try_new_with_flow_control(reg, tx, rx, rts, cts, config, cmu) {
+            Ok(serial) => serial,
+            Err(e) => panic!("{e}"),
         }
     }
+
+    /// Create a new non-blocking serial with RTS/CTS pads, or return the closest
+    /// baud rate if the requested one cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM).
+    pub fn try_new_with_flow_control(
+        reg: &'a RegisterBlock,
+        tx: TX,
+        rx: RX,
+        rts: RTS,
+        cts: CTS,
+        config: UartConfig,
+        cmu: &mut Cmu,
+    ) -> Result<Self, BaudRateError> {
+        Self::init(reg, (tx, cts), (rx, rts), config, true, cmu)
+    }
 }
 
 impl<'a, const I: u8, TX, RX> embedded_io_async::ErrorType for AsyncSerial<'a, I, TX, RX>
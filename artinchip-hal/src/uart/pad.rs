@@ -3,3 +3,12 @@
 pub trait UartPad<const I: u8> {}
 pub trait Transmit<const I: u8>: UartPad<I> {}
 pub trait Receive<const I: u8>: UartPad<I> {}
+/// Request To Send output, asserted while the receiver can take data.
+pub trait Rts<const I: u8>: UartPad<I> {}
+/// Clear To Send input, gating the transmitter.
+pub trait Cts<const I: u8>: UartPad<I> {}
+
+/// A data pad paired with its flow control pad, `(TX, CTS)` or `(RX, RTS)`.
+impl<const I: u8, A: UartPad<I>, B: UartPad<I>> UartPad<I> for (A, B) {}
+impl<const I: u8, TX: Transmit<I>, CTS: Cts<I>> Transmit<I> for (TX, CTS) {}
+impl<const I: u8, RX: Receive<I>, RTS: Rts<I>> Receive<I> for (RX, RTS) {}
@@ -0,0 +1,73 @@
+//! RS-485 half-duplex mode.
+
+use super::config::Rs485Config;
+use super::register::{RegisterBlock, Rs485ControlMode, Rs485SlaveMode};
+use uart16550::{ModemControl, PARITY};
+
+/// Function mode field of `MCR` (`UART_FUNCTION`).
+const MCR_FUNCTION: u8 = 0x3 << 6;
+/// RS-485 function mode.
+const MCR_FUNCTION_RS485: u8 = 0x2 << 6;
+
+/// Switch the UART to RS-485 mode with hardware driven DE.
+///
+/// Must be called after the line control register is configured, as the
+/// address match mode takes over the parity bit as the 9th (address) bit.
+pub(crate) fn configure(reg: &RegisterBlock, config: &Rs485Config) {
+    let uart16550 = &reg.uart16550;
+
+    // Select RS-485 function mode
+    let mcr = uart16550.mcr().read();
+    uart16550
+        .mcr()
+        .write(ModemControl((mcr.0 & !MCR_FUNCTION) | MCR_FUNCTION_RS485));
+
+    unsafe {
+        // DE timing around each transmitted frame
+        reg.rs485_de.modify(|v| {
+            v.set_de_assert_time(config.de_assert_time)
+                .set_de_deassert_time(config.de_deassert_time)
+        });
+
+        // Multidrop address match on the 9th bit, or receive all bytes
+        match config.address {
+            Some(address) => {
+                reg.rs485_addr_match
+                    .modify(|v| v.set_match_address(address as u32));
+                reg.rs485_ctl.modify(|v| {
+                    v.set_rs485_control_mode(Rs485ControlMode::Hardware)
+                        .set_rs485_slave_mode(Rs485SlaveMode::AAD)
+                        .disable_receive_all_before_addr()
+                        .disable_receive_all_after_addr()
+                        .clear_rs485_address_matched()
+                        .clear_rs485_address_detected()
+                });
+            }
+            None => {
+                reg.rs485_ctl.modify(|v| {
+                    v.set_rs485_control_mode(Rs485ControlMode::Hardware)
+                        .set_rs485_slave_mode(Rs485SlaveMode::NMM)
+                        .enable_receive_all_before_addr()
+                        .enable_receive_all_after_addr()
+                });
+            }
+        }
+
+        // Wait for an idle bus before transmitting
+        reg.rs485_bus_idle_check.modify(|v| {
+            if config.bus_idle_check {
+                v.enable_bus_idle_check()
+            } else {
+                v.disable_bus_idle_check()
+            }
+        });
+    }
+
+    // Data bytes carry a 0 in the 9th bit, sent as stick parity (PEN and EPS set)
+    if config.address.is_some() {
+        let lcr = uart16550.lcr().read();
+        uart16550
+            .lcr()
+            .write(lcr.set_parity(PARITY::ODD).enable_stick_parity());
+    }
+}
@@ -3,13 +3,19 @@
 use super::blocking::BlockingSerial;
 use super::config::UartConfig;
 use super::pad::UartPad;
-use super::pad::{Receive, Transmit};
+use super::pad::{Cts, Receive, Rts, Transmit};
 use crate::cmu::Cmu;
 #[cfg(feature = "clic-interrupts")]
 use {super::instance::*, super::non_blocking::*, crate::interrupt::clic::typelevel};
 
 pub trait UartExt<'a, const I: u8> {
     /// Greats a blocking UART interface with the specified pads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM), or if flow control
+    /// is enabled, which needs the RTS and CTS pads.
     fn new_blocking<TX, RX>(
         self,
         tx: TX,
@@ -20,7 +26,33 @@ pub trait UartExt<'a, const I: u8> {
     where
         TX: UartPad<I> + Transmit<I>,
         RX: UartPad<I> + Receive<I>;
+    /// Creates a blocking UART interface with RTS/CTS auto flow control.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM).
+    fn new_blocking_with_flow_control<TX, RX, RTS, CTS>(
+        self,
+        tx: TX,
+        rx: RX,
+        rts: RTS,
+        cts: CTS,
+        config: UartConfig,
+        cmu: &mut Cmu,
+    ) -> BlockingSerial<'a, I, (TX, CTS), (RX, RTS)>
+    where
+        TX: UartPad<I> + Transmit<I>,
+        RX: UartPad<I> + Receive<I>,
+        RTS: UartPad<I> + Rts<I>,
+        CTS: UartPad<I> + Cts<I>;
     /// Creates a non-blocking UART interface with the specified pads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM), or if flow control
+    /// is enabled, which needs the RTS and CTS pads.
     #[cfg(feature = "clic-interrupts")]
     fn new_async<TX, RX, IRQS>(
         self,
@@ -37,4 +69,31 @@ pub trait UartExt<'a, const I: u8> {
         Uart<I>: UartInterrupt<I>,
         AsyncUartHandler<I>: typelevel::Handler<<Uart<I> as UartInterrupt<I>>::Interrupt>,
         IRQS: typelevel::Binding<<Uart<I> as UartInterrupt<I>>::Interrupt, AsyncUartHandler<I>>;
+    /// Creates a non-blocking UART interface with RTS/CTS auto flow control.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the baud rate cannot be generated within
+    /// [`MAX_BAUD_ERROR_PPM`](super::MAX_BAUD_ERROR_PPM).
+    #[cfg(feature = "clic-interrupts")]
+    #[allow(clippy::too_many_arguments)]
+    fn new_async_with_flow_control<TX, RX, RTS, CTS, IRQS>(
+        self,
+        tx: TX,
+        rx: RX,
+        rts: RTS,
+        cts: CTS,
+        config: UartConfig,
+        cmu: &mut Cmu,
+        _irqs: IRQS,
+    ) -> AsyncSerial<'a, I, (TX, CTS), (RX, RTS)>
+    where
+        Self: Sized + UartInterrupt<I>,
+        TX: UartPad<I> + Transmit<I>,
+        RX: UartPad<I> + Receive<I>,
+        RTS: UartPad<I> + Rts<I>,
+        CTS: UartPad<I> + Cts<I>,
+        Uart<I>: UartInterrupt<I>,
+        AsyncUartHandler<I>: typelevel::Handler<<Uart<I> as UartInterrupt<I>>::Interrupt>,
+        IRQS: typelevel::Binding<<Uart<I> as UartInterrupt<I>>::Interrupt, AsyncUartHandler<I>>;
 }
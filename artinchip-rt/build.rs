@@ -4,42 +4,119 @@ fn main() {
     let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
     let ld = &out.join("artinchip-rt.ld");
 
-    std::fs::write(ld, LINKER_SCRIPT).unwrap();
+    let feature = |name: &str| {
+        let var = format!("CARGO_FEATURE_{}", name.to_uppercase().replace('-', "_"));
+        env::var_os(var).is_some()
+    };
+    let d21x = feature("d21x");
+    if d21x && feature("interrupts") {
+        panic!("artinchip-rt: D21x has no CLIC, the `interrupts` feature is not supported");
+    }
+
+    // Application layouts available on the chip
+    let (pbp_memory, layouts) = if d21x {
+        (D21X_PBP_MEMORY, D21X_APP_LAYOUTS)
+    } else {
+        (PBP_MEMORY, APP_LAYOUTS)
+    };
+    for app in ["app-sram", "app-xip", "app-psram", "app-dram"] {
+        if feature(app) && !layouts.iter().any(|(name, _)| *name == app) {
+            panic!("artinchip-rt: the `{app}` feature is not supported on this chip");
+        }
+    }
+    let app_layouts: Vec<_> = layouts.iter().filter(|(name, _)| feature(name)).collect();
+
+    let (memory, sections) = match app_layouts.as_slice() {
+        [] => (pbp_memory, PBP_LINKER_SCRIPT),
+        [(_, memory)] => (*memory, APP_LINKER_SCRIPT),
+        _ => panic!("artinchip-rt: only one of the `app-*` features can be enabled"),
+    };
+    // With `memory-x` the regions come from the `memory.x` of the downstream crate
+    let memory = if feature("memory-x") {
+        b"INCLUDE memory.x\n".as_slice()
+    } else {
+        memory
+    };
+    // The C906 PMP has a 4 KiB granularity, the guard must fill whole granules
+    let guard: &[u8] = if d21x && feature("stack-guard") {
+        b"_stack_guard_size = 4K;\n"
+    } else {
+        b"_stack_guard_size = 64;\n"
+    };
+    let mut script = [guard, memory, sections].concat();
+    // The BROM only accepts a PBP inside its PBP area
+    if app_layouts.is_empty() && !d21x {
+        script.extend(PBP_AREA_ASSERT);
+    }
+    // Exceptions without a user hook fall back to the default report
+    for exception in EXCEPTIONS {
+        script.extend(format!("\nPROVIDE(__exception_{exception} = __default_exception);").bytes());
+    }
+    // Interrupts bound with `#[interrupt]`, unless `clic_bind_interrupts!` defines
+    // `_init_vector_table`, which would leave the `#[interrupt]` handlers out
+    if feature("interrupts") {
+        script.extend(b"\nPROVIDE(_init_vector_table = _default_init_vector_table);");
+        script.extend(INTERRUPT_BINDING_ASSERT);
+    }
+    std::fs::write(ld, script).unwrap();
 
     println!("cargo:rustc-link-arg=-T{}", ld.display());
     println!("cargo:rustc-link-search={}", out.display());
 }
 
-const LINKER_SCRIPT: &[u8] = b"OUTPUT_ARCH(riscv)
+macro_rules! exceptions {
+    ($($name:ident = $code:literal,)*) => {
+        /// Exceptions that can be hooked with `#[exception]`, see `core::trap`.
+        const EXCEPTIONS: &[&str] = &[$(stringify!($name)),*];
+    };
+}
+artinchip_rt_macros::for_each_exception!(exceptions);
+
+/// Pre-Boot Program, loaded by the BROM into SRAM right below the BROM stack.
+///
+/// The PBP header is placed at the region origin, directly followed by the entry.
+const PBP_MEMORY: &[u8] = b"MEMORY {
+    SRAM : ORIGIN = 0x30044000 - 0x8, LENGTH = 112K + 0x8
+}
+REGION_ALIAS(\"REGION_TEXT\", SRAM);
+REGION_ALIAS(\"REGION_DATA\", SRAM);
+";
+
+/// Pre-Boot Program sections, placed into `REGION_TEXT` and `REGION_DATA`.
+const PBP_LINKER_SCRIPT: &[u8] = b"OUTPUT_ARCH(riscv)
 ENTRY(_start)
+PROVIDE(_stack_size = 2K);
+PROVIDE(_heap_size = 0);
 SECTIONS {
-    . = 0x30044000 - 0x8;
-	.head : ALIGN(4) {
+    .head : ALIGN(4) {
         KEEP(*(.head.pbp))
-	}
-    . = 0x30044000;
+    } > REGION_TEXT
     .text : ALIGN(4) {
         *(.text.entry)
         *(.text .text.*)
-    }
+    } > REGION_TEXT
     .rodata : ALIGN(4) {
         srodata = .;
         *(.rodata .rodata.*)
         *(.srodata .srodata.*)
+        . = ALIGN(8);
+        __sclic_handlers = .;
+        KEEP(*(.clic.handlers))
+        __eclic_handlers = .;
         . = ALIGN(4);
         erodata = .;
-    }
+    } > REGION_TEXT
     .clic.vector_table (NOLOAD) : ALIGN(64) {
         KEEP(*(.clic.vector_table))
         . = ALIGN(64);
-    }
+    } > REGION_DATA
     .data : ALIGN(4) {
         sdata = .;
         *(.data .data.*)
         *(.sdata .sdata.*)
         . = ALIGN(4);
         edata = .;
-    }
+    } > REGION_DATA
     sidata = LOADADDR(.data);
     .bss (NOLOAD) : ALIGN(4) {
         *(.bss.uninit)
@@ -47,7 +124,159 @@ SECTIONS {
         *(.bss .bss.*)
         *(.sbss .sbss.*)
         ebss = .;
+    } > REGION_DATA
+    .stack (NOLOAD) : ALIGN(64) {
+        . = ALIGN(_stack_guard_size);
+        _sguard = .;
+        . += _stack_guard_size; /* STACK_GUARD_SIZE */
+        _sstack = .;
+        . += _stack_size;
+        . = ALIGN(16);
+        _estack = .;
+    } > REGION_DATA
+    .heap (NOLOAD) : ALIGN(4) {
+        _sheap = .;
+        . += _heap_size;
+        _eheap = .;
+    } > REGION_DATA
+    /DISCARD/ : {
+        *(.eh_frame)
     }
+}
+ASSERT(ADDR(.text) == ADDR(.head) + 0x8, \"PBP header must directly precede `_start`\");
+";
+
+/// Pre-Boot Program area of the E907 BROM.
+///
+/// Whatever the regions are, the image must stay inside the SRAM the BROM
+/// accepts a PBP in, which ends at 0x30060000.
+const PBP_AREA_ASSERT: &[u8] = b"ASSERT(ADDR(.head) >= 0x30044000 - 0x8 && _eheap <= 0x30060000,
+    \"PBP does not fit into the BROM PBP area 0x30043FF8..0x30060000\");
+";
+
+const INTERRUPT_BINDING_ASSERT: &[u8] = b"
+ASSERT(_init_vector_table == _default_init_vector_table || __sclic_handlers == __eclic_handlers,
+    \"interrupts are bound with both #[interrupt] and clic_bind_interrupts!, use one of them\");";
+
+/// Second-stage application layouts of the E907 chips.
+const APP_LAYOUTS: &[(&str, &[u8])] = &[
+    ("app-sram", APP_SRAM_MEMORY),
+    ("app-xip", APP_XIP_MEMORY),
+    ("app-psram", APP_PSRAM_MEMORY),
+];
+
+/// Second-stage application loaded by the PBP into SRAM behind the PBP area.
+const APP_SRAM_MEMORY: &[u8] = b"MEMORY {
+    SRAM : ORIGIN = 0x30060000, LENGTH = 128K
+}
+REGION_ALIAS(\"REGION_TEXT\", SRAM);
+REGION_ALIAS(\"REGION_DATA\", SRAM);
+";
+
+/// Second-stage application executed in place from SPI NOR through the XSPI
+/// memory window, with data in SRAM.
+const APP_XIP_MEMORY: &[u8] = b"MEMORY {
+    XIP  : ORIGIN = 0x80000000, LENGTH = 16M
+    SRAM : ORIGIN = 0x30060000, LENGTH = 128K
+}
+REGION_ALIAS(\"REGION_TEXT\", XIP);
+REGION_ALIAS(\"REGION_DATA\", SRAM);
+";
+
+/// Second-stage application loaded by the PBP into external PSRAM.
+const APP_PSRAM_MEMORY: &[u8] = b"MEMORY {
+    PSRAM : ORIGIN = 0x40000000, LENGTH = 8M
+}
+REGION_ALIAS(\"REGION_TEXT\", PSRAM);
+REGION_ALIAS(\"REGION_DATA\", PSRAM);
+";
+
+/// D21x Pre-Boot Program, loaded by the C906 BROM to the start of SRAM.
+const D21X_PBP_MEMORY: &[u8] = b"MEMORY {
+    SRAM : ORIGIN = 0x00100000, LENGTH = 96K
+}
+REGION_ALIAS(\"REGION_TEXT\", SRAM);
+REGION_ALIAS(\"REGION_DATA\", SRAM);
+";
+
+/// Second-stage application layouts of the D21x.
+const D21X_APP_LAYOUTS: &[(&str, &[u8])] = &[
+    ("app-sram", D21X_APP_SRAM_MEMORY),
+    ("app-dram", D21X_APP_DRAM_MEMORY),
+];
+
+/// D21x second-stage application loaded by the PBP into SRAM behind the PBP area.
+const D21X_APP_SRAM_MEMORY: &[u8] = b"MEMORY {
+    SRAM : ORIGIN = 0x00118000, LENGTH = 160K
+}
+REGION_ALIAS(\"REGION_TEXT\", SRAM);
+REGION_ALIAS(\"REGION_DATA\", SRAM);
+";
+
+/// D21x second-stage application loaded into DRAM once the PBP has initialized it.
+const D21X_APP_DRAM_MEMORY: &[u8] = b"MEMORY {
+    DRAM : ORIGIN = 0x40000000, LENGTH = 64M
+}
+REGION_ALIAS(\"REGION_TEXT\", DRAM);
+REGION_ALIAS(\"REGION_DATA\", DRAM);
+";
+
+/// Second-stage application sections, placed into `REGION_TEXT` and `REGION_DATA`.
+///
+/// `.data` is loaded right behind `.rodata` and copied to its run address by `_start`.
+const APP_LINKER_SCRIPT: &[u8] = b"OUTPUT_ARCH(riscv)
+ENTRY(_start)
+PROVIDE(_stack_size = 4K);
+PROVIDE(_heap_size = 0);
+SECTIONS {
+    .text : ALIGN(4) {
+        KEEP(*(.text.entry))
+        *(.text .text.*)
+    } > REGION_TEXT
+    .rodata : ALIGN(4) {
+        srodata = .;
+        *(.rodata .rodata.*)
+        *(.srodata .srodata.*)
+        . = ALIGN(8);
+        __sclic_handlers = .;
+        KEEP(*(.clic.handlers))
+        __eclic_handlers = .;
+        . = ALIGN(4);
+        erodata = .;
+    } > REGION_TEXT
+    .data : ALIGN(4) {
+        sdata = .;
+        *(.data .data.*)
+        *(.sdata .sdata.*)
+        . = ALIGN(4);
+        edata = .;
+    } > REGION_DATA AT > REGION_TEXT
+    sidata = LOADADDR(.data);
+    .bss (NOLOAD) : ALIGN(4) {
+        *(.bss.uninit)
+        sbss = .;
+        *(.bss .bss.*)
+        *(.sbss .sbss.*)
+        ebss = .;
+    } > REGION_DATA
+    .stack (NOLOAD) : ALIGN(64) {
+        . = ALIGN(_stack_guard_size);
+        _sguard = .;
+        . += _stack_guard_size; /* STACK_GUARD_SIZE */
+        _sstack = .;
+        . += _stack_size;
+        . = ALIGN(16);
+        _estack = .;
+    } > REGION_DATA
+    .heap (NOLOAD) : ALIGN(4) {
+        _sheap = .;
+        . += _heap_size;
+        _eheap = .;
+    } > REGION_DATA
+    .clic.vector_table (NOLOAD) : ALIGN(64) {
+        KEEP(*(.clic.vector_table))
+        . = ALIGN(64);
+    } > REGION_DATA
     /DISCARD/ : {
         *(.eh_frame)
     }
@@ -1,9 +1,10 @@
 //! ArtInChip ROM runtime procedural macros.
 
-use proc_macro2::Span;
-use quote::quote;
+use proc_macro2::{Literal, Span};
+use quote::{quote, quote_spanned};
 use syn::{
-    FnArg, ItemFn, ReturnType, Type, Visibility, parse, parse_macro_input, spanned::Spanned,
+    FnArg, Ident, ItemFn, Path, ReturnType, Type, Visibility, parse, parse_macro_input,
+    spanned::Spanned,
 };
 
 use proc_macro::TokenStream;
@@ -132,3 +133,342 @@ pub fn pbp_entry(args: TokenStream, input: TokenStream) -> TokenStream {
     )
     .into()
 }
+
+/// Second-stage application entry.
+///
+/// The function never returns. It takes no parameter, or the handoff the
+/// loader passed in `a0`, `None` if the loader passed none:
+///
+/// ```rust
+/// use artinchip_rt::core::handoff::Handoff;
+///
+/// #[entry]
+/// fn main(handoff: Option<&'static Handoff>) -> !
+/// ```
+#[proc_macro_attribute]
+pub fn entry(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+
+    // check the function arguments
+    if f.sig.inputs.len() > 1 {
+        return parse::Error::new(
+            f.sig.inputs.span(),
+            "`#[entry]` function should include at most one parameter",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if let Some(arg) = f.sig.inputs.first() {
+        match arg {
+            FnArg::Typed(t) if matches!(&*t.ty, Type::Path(_)) => {}
+            _ => {
+                return parse::Error::new(
+                    arg.span(),
+                    "artinchip-rt-macros: argument type must be `Option<&'static Handoff>`",
+                )
+                .to_compile_error()
+                .into();
+            }
+        }
+    }
+
+    // check the function signature
+    let valid_signature = f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && matches!(&f.sig.output, ReturnType::Type(_, ty) if matches!(&**ty, Type::Never(_)));
+
+    if !valid_signature {
+        return parse::Error::new(
+            f.span(),
+            "`#[entry]` function must have signature `[unsafe] fn([handoff: Option<&'static Handoff>]) -> !`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if !args.is_empty() {
+        return parse::Error::new(Span::call_site(), "This attribute accepts no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let args = f.sig.inputs;
+    let stmts = f.block.stmts;
+    let ident = f.sig.ident;
+    let handoff = if args.is_empty() {
+        quote!()
+    } else {
+        quote!(unsafe { ::artinchip_rt::core::handoff::Handoff::from_arg(arg0) })
+    };
+    let call = if unsafety.is_some() {
+        quote!(unsafe { __artinchip_rt__main(#handoff) })
+    } else {
+        quote!(__artinchip_rt__main(#handoff))
+    };
+
+    quote!(
+        #[unsafe(export_name = "main")]
+        #(#attrs)*
+        pub extern "C" fn #ident(arg0: usize) -> ! {
+            let _ = arg0;
+            #[allow(non_snake_case)]
+            #[inline]
+            #(#attrs)*
+            #unsafety fn __artinchip_rt__main(#args) -> ! {
+                #(#stmts)*
+            }
+            #call
+        }
+    )
+    .into()
+}
+
+/// Exceptions that can be hooked, named after their `mcause` exception code.
+///
+/// The runtime generates `artinchip_rt::core::trap::Exception` and the
+/// default hooks of its linker script from this list with [`for_each_exception`].
+const EXCEPTIONS: &[(&str, u8)] = &[
+    ("InstructionMisaligned", 0),
+    ("InstructionFault", 1),
+    ("IllegalInstruction", 2),
+    ("Breakpoint", 3),
+    ("LoadMisaligned", 4),
+    ("LoadFault", 5),
+    ("StoreMisaligned", 6),
+    ("StoreFault", 7),
+    ("UserEnvCall", 8),
+    ("SupervisorEnvCall", 9),
+    ("MachineEnvCall", 11),
+    ("InstructionPageFault", 12),
+    ("LoadPageFault", 13),
+    ("StorePageFault", 15),
+];
+
+/// Invoke `callback! { Name = code, ... }` with every exception `#[exception]` accepts.
+#[doc(hidden)]
+#[proc_macro]
+pub fn for_each_exception(input: TokenStream) -> TokenStream {
+    let callback = parse_macro_input!(input as Path);
+    exception_list(&callback).into()
+}
+
+fn exception_list(callback: &Path) -> proc_macro2::TokenStream {
+    let names = EXCEPTIONS
+        .iter()
+        .map(|(name, _)| Ident::new(name, Span::call_site()));
+    let codes = EXCEPTIONS
+        .iter()
+        .map(|(_, code)| Literal::u8_unsuffixed(*code));
+    quote!(#callback! { #(#names = #codes,)* })
+}
+
+/// Exception hook.
+///
+/// The argument names the exception. The hook gets the registers saved on
+/// trap entry and returns whether it handled the exception; the trap then
+/// returns to `frame.mepc`. Otherwise the default trap handler reports it.
+///
+/// ```rust
+/// use artinchip_rt::core::trap::TrapFrame;
+///
+/// #[exception(IllegalInstruction)]
+/// fn emulate(frame: &mut TrapFrame) -> bool
+/// ```
+#[proc_macro_attribute]
+pub fn exception(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+    let name = parse_macro_input!(args as Ident);
+
+    if !EXCEPTIONS.iter().any(|(e, _)| name == e) {
+        let names: Vec<_> = EXCEPTIONS.iter().map(|(e, _)| *e).collect();
+        return parse::Error::new(
+            name.span(),
+            format!(
+                "unknown exception `{name}`, expected one of: {}",
+                names.join(", ")
+            ),
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // check the function arguments
+    if f.sig.inputs.len() != 1 {
+        return parse::Error::new(
+            f.sig.inputs.span(),
+            "`#[exception]` function should include exactly one parameter",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    match &f.sig.inputs[0] {
+        FnArg::Typed(t) if matches!(&*t.ty, Type::Reference(r) if r.mutability.is_some()) => {}
+        arg => {
+            return parse::Error::new(
+                arg.span(),
+                "artinchip-rt-macros: argument type must be `&mut TrapFrame`",
+            )
+            .to_compile_error()
+            .into();
+        }
+    }
+
+    // check the function signature
+    let valid_signature = f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && matches!(&f.sig.output, ReturnType::Type(_, ty) if matches!(&**ty, Type::Path(p) if p.path.is_ident("bool")));
+
+    if !valid_signature {
+        return parse::Error::new(
+            f.span(),
+            "`#[exception]` function must have signature `[unsafe] fn(frame: &mut TrapFrame) -> bool`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let args = f.sig.inputs;
+    let stmts = f.block.stmts;
+    let ident = f.sig.ident;
+    let export_name = format!("__exception_{name}");
+    let call = if unsafety.is_some() {
+        quote!(unsafe { __artinchip_rt__exception(frame) })
+    } else {
+        quote!(__artinchip_rt__exception(frame))
+    };
+
+    quote!(
+        #[unsafe(export_name = #export_name)]
+        #(#attrs)*
+        pub extern "C" fn #ident(frame: &mut ::artinchip_rt::core::trap::TrapFrame) -> bool {
+            #[allow(non_snake_case)]
+            #[inline]
+            #(#attrs)*
+            #unsafety fn __artinchip_rt__exception(#args) -> bool {
+                #(#stmts)*
+            }
+            #call
+        }
+    )
+    .into()
+}
+
+/// Interrupt handler.
+///
+/// The function is named after the interrupt in the `typelevel` list of the
+/// selected chip; a name the chip does not have fails to compile. The crate
+/// must enable `#![feature(abi_riscv_interrupt)]`.
+///
+/// ```rust
+/// #[interrupt]
+/// fn GPIO_GRP_A()
+/// ```
+#[proc_macro_attribute]
+pub fn interrupt(args: TokenStream, input: TokenStream) -> TokenStream {
+    let f = parse_macro_input!(input as ItemFn);
+
+    // check the function signature
+    let valid_signature = f.sig.constness.is_none()
+        && f.sig.asyncness.is_none()
+        && f.vis == Visibility::Inherited
+        && f.sig.abi.is_none()
+        && f.sig.inputs.is_empty()
+        && f.sig.generics.params.is_empty()
+        && f.sig.generics.where_clause.is_none()
+        && f.sig.variadic.is_none()
+        && matches!(f.sig.output, ReturnType::Default);
+
+    if !valid_signature {
+        return parse::Error::new(
+            f.span(),
+            "`#[interrupt]` function must have signature `[unsafe] fn()`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if !args.is_empty() {
+        return parse::Error::new(Span::call_site(), "This attribute accepts no arguments")
+            .to_compile_error()
+            .into();
+    }
+
+    let attrs = f.attrs;
+    let unsafety = f.sig.unsafety;
+    let stmts = f.block.stmts;
+    let ident = f.sig.ident;
+    let export_name = format!("__irq_handler_{ident}");
+    // resolving the name in `typelevel` rejects interrupts the chip does not have
+    let irq = quote_spanned!(ident.span()=> ::artinchip_rt::interrupt::typelevel::#ident);
+    let call = if unsafety.is_some() {
+        quote!(unsafe { __artinchip_rt__interrupt() })
+    } else {
+        quote!(__artinchip_rt__interrupt())
+    };
+
+    quote!(
+        #[allow(non_snake_case)]
+        #[unsafe(export_name = #export_name)]
+        #(#attrs)*
+        pub extern "riscv-interrupt-m" fn #ident() {
+            #[allow(non_snake_case)]
+            #[inline]
+            #(#attrs)*
+            #unsafety fn __artinchip_rt__interrupt() {
+                #(#stmts)*
+            }
+            #call
+        }
+        const _: () = {
+            #[unsafe(link_section = ".clic.handlers")]
+            #[used]
+            static VECTOR: ::artinchip_rt::interrupt::Vector =
+                ::artinchip_rt::interrupt::Vector::new::<#irq>(#ident);
+        };
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EXCEPTIONS, exception_list};
+
+    #[test]
+    fn exception_codes() {
+        for (i, (name, code)) in EXCEPTIONS.iter().enumerate() {
+            // Standard exception codes, 10 and 14 are reserved
+            assert!(*code < 16 && *code != 10 && *code != 14, "{name} = {code}");
+            assert!(
+                !EXCEPTIONS[..i].iter().any(|(n, c)| n == name || c == code),
+                "duplicate {name} = {code}"
+            );
+        }
+    }
+
+    #[test]
+    fn for_each_exception() {
+        let tokens = exception_list(&syn::parse_quote!(exceptions)).to_string();
+        assert!(tokens.starts_with("exceptions ! {"), "{tokens}");
+        assert!(tokens.contains("{ InstructionMisaligned = 0 , InstructionFault = 1 ,"));
+        assert!(tokens.ends_with("LoadPageFault = 13 , StorePageFault = 15 , }"));
+        for (name, code) in EXCEPTIONS {
+            assert!(tokens.contains(&format!("{name} = {code} ,")), "{name}");
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Second-stage application runtime.
+//!
+//! Enabled by one of the `app-sram`, `app-xip` or `app-psram` features, or
+//! `app-sram` and `app-dram` on the D21x, which also select the linker
+//! script. Unlike a PBP, the application is started by the PBP (or any other
+//! loader) and may run from a different address than it is stored at, so
+//! `_start` copies `.data` from its load address.
+//!
+//! The application provides the entry function with [`entry`](crate::entry):
+//!
+//! ```ignore
+//! use artinchip_rt::{core::handoff::Handoff, entry};
+//!
+//! #[entry]
+//! fn main(handoff: Option<&'static Handoff>) -> ! {
+//!     loop {}
+//! }
+//! ```
+//!
+//! `handoff` is read from the `a0` register value the loader jumped with, see
+//! [`handoff`](crate::core::handoff).
+use crate::memory::STACK_PAINT;
+use core::arch::naked_asm;
+
+#[unsafe(naked)]
+#[unsafe(no_mangle)]
+#[unsafe(link_section = ".text.entry")]
+pub extern "C" fn _start() {
+    naked_asm!(
+        // 1. Disable interrupt and keep the loader argument
+        "   csrw    mie, zero
+            mv      s0, a0",
+
+        // 2. Hart specific initialization, see `hart_init!`
+        hart_init!(),
+
+        // 3. Initialize float point unit
+        "   li      t0, 0x4000
+            li      t1, 0x2000
+            csrc    mstatus, t0
+            csrs    mstatus, t1
+            csrw    fcsr, zero",
+
+        // 4. Copy `.data` section from its load address
+        "   la      t0, sdata
+            la      t1, edata
+            la      t2, sidata
+            beq     t0, t2, 2f
+        1:  bgeu    t0, t1, 2f
+            lw      t3, 0(t2)
+            sw      t3, 0(t0)
+            addi    t0, t0, 4
+            addi    t2, t2, 4
+            j       1b",
+
+        // 5. Clear `.bss` section
+        "2: la      t0, sbss
+            la      t1, ebss
+        1:  bgeu    t0, t1, 2f
+            sw      zero, 0(t0)
+            addi    t0, t0, 4
+            j       1b",
+
+        // 6. Paint guard and stack to detect overflows and measure usage
+        "2: la      t0, _sguard
+            la      t1, _estack
+            li      t2, {paint}
+        1:  bgeu    t0, t1, 2f
+            sw      t2, 0(t0)
+            addi    t0, t0, 4
+            j       1b",
+
+        // 7. Prepare programming language stack
+        "2: la      sp, _estack",
+
+        // 8. Init stack guard and vector table, enable caches before main
+        "   call    {init_stack_guard}",
+        "   call    {init_vector_table}",
+        "   call    {enable_cache}",
+        "   fence.i",
+
+        // 9. Start Rust main function with the loader argument
+        "   mv      a0, s0
+            call    {main}",
+
+        // 10. Platform halt (by loop-wfi) if main function returns
+        hart_halt!(),
+
+        main              =   sym main,
+        init_stack_guard  =   sym _init_stack_guard,
+        init_vector_table =   sym _init_vector_table,
+        enable_cache      =   sym _enable_cache,
+        paint             =   const STACK_PAINT,
+    )
+}
+
+unsafe extern "C" {
+    unsafe fn main(arg0: usize);
+    unsafe fn _init_stack_guard();
+    unsafe fn _init_vector_table();
+    unsafe fn _enable_cache();
+}
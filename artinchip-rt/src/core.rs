@@ -1,3 +1,5 @@
 pub mod boot_rom;
 pub mod cache;
+pub mod crash;
+pub mod handoff;
 pub mod trap;
@@ -0,0 +1,130 @@
+//! Crash record kept in the RTC across a watchdog reset.
+//!
+//! The RTC keeps 15 `SYS_BAK` bytes through a watchdog reset. With the `Reset`
+//! [`FatalPolicy`](super::trap::FatalPolicy), the trap handler saves a
+//! [`CrashRecord`] there before it resets:
+//!
+//! | Byte   | Content                                          |
+//! |:-------|:-------------------------------------------------|
+//! | 0      | Magic `0xC5`                                     |
+//! | 1      | Exception code, bit 7 set for an interrupt       |
+//! | 2..6   | `mepc`                                           |
+//! | 6..10  | `mtval`                                          |
+//! | 10..14 | `ra`                                             |
+//! | 14     | Checksum, the bytes 0..15 sum to zero            |
+//!
+//! Addresses are stored as 32 bits.
+//!
+//! The M6800 has no RTC, so the reset policy is not available there.
+
+use artinchip_hal::cmu;
+use artinchip_hal::rtc;
+
+/// Trap state saved across a reset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrashRecord {
+    /// Whether the trap was an interrupt.
+    pub interrupt: bool,
+    /// Exception or interrupt code of `mcause`.
+    pub code: u8,
+    /// Address of the trapping instruction.
+    pub mepc: u32,
+    /// Trap value, e.g. the faulting address.
+    pub mtval: u32,
+    /// Return address of the trapping function.
+    pub ra: u32,
+}
+
+impl CrashRecord {
+    const MAGIC: u8 = 0xC5;
+    const WRITE_EN_KEY: u8 = 0xAC;
+
+    /// Encode into the `SYS_BAK` bytes.
+    pub fn to_bytes(&self) -> [u8; 15] {
+        let mut bytes = [0u8; 15];
+        bytes[0] = Self::MAGIC;
+        bytes[1] = (self.code & 0x7F) | ((self.interrupt as u8) << 7);
+        bytes[2..6].copy_from_slice(&self.mepc.to_le_bytes());
+        bytes[6..10].copy_from_slice(&self.mtval.to_le_bytes());
+        bytes[10..14].copy_from_slice(&self.ra.to_le_bytes());
+        bytes[14] = bytes[..14]
+            .iter()
+            .fold(0u8, |sum, b| sum.wrapping_add(*b))
+            .wrapping_neg();
+        bytes
+    }
+
+    /// Decode from the `SYS_BAK` bytes, `None` if they hold no valid record.
+    pub fn from_bytes(bytes: &[u8; 15]) -> Option<Self> {
+        let sum = bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b));
+        if bytes[0] != Self::MAGIC || sum != 0 {
+            return None;
+        }
+        let word =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        Some(Self {
+            interrupt: bytes[1] & 0x80 != 0,
+            code: bytes[1] & 0x7F,
+            mepc: word(2),
+            mtval: word(6),
+            ra: word(10),
+        })
+    }
+
+    /// Save the record into the RTC backup registers.
+    pub fn save(&self, rtc: &rtc::RegisterBlock, cmu: &cmu::RegisterBlock) {
+        unsafe {
+            cmu.clock_rtc.modify(|v| v.enable_bus_clk());
+            rtc.write_key
+                .modify(|v| v.set_write_key(Self::WRITE_EN_KEY));
+            for (reg, byte) in rtc.sys_backup.iter().zip(self.to_bytes()) {
+                reg.modify(|v| v.set_sys_bak(byte));
+            }
+            rtc.write_key.modify(|v| v.set_write_key(0));
+        }
+    }
+
+    /// Read the record saved before the last reset and clear it.
+    pub fn take(rtc: &rtc::RegisterBlock, cmu: &cmu::RegisterBlock) -> Option<Self> {
+        unsafe { cmu.clock_rtc.modify(|v| v.enable_bus_clk()) };
+        let mut bytes = [0u8; 15];
+        for (byte, reg) in bytes.iter_mut().zip(&rtc.sys_backup) {
+            *byte = reg.read().sys_bak();
+        }
+        let record = Self::from_bytes(&bytes)?;
+        unsafe {
+            rtc.write_key
+                .modify(|v| v.set_write_key(Self::WRITE_EN_KEY));
+            rtc.sys_backup[0].modify(|v| v.set_sys_bak(0));
+            rtc.write_key.modify(|v| v.set_write_key(0));
+        }
+        Some(record)
+    }
+}
+
+/// Save `record` and reset the chip through the watchdog.
+#[cfg(any(feature = "d12x", feature = "d13x", feature = "d21x", feature = "g73x"))]
+pub(crate) fn reset_with_record(record: &CrashRecord) -> ! {
+    use crate::Peripherals;
+    #[cfg(feature = "d12x")]
+    use crate::soc::d12x::{RTC, WDOG};
+    #[cfg(feature = "d13x")]
+    use crate::soc::d13x::{RTC, WDOG};
+    #[cfg(feature = "d21x")]
+    use crate::soc::d21x::{RTC, WDOG};
+    #[cfg(feature = "g73x")]
+    use crate::soc::g73x::{RTC, WDOG};
+    use artinchip_hal::wdog::WdogDriver;
+    use log::error;
+
+    // The peripherals are not handed back, nothing else runs after the trap
+    let (rtc, wdog) = unsafe { (&*RTC::ptr(), &*WDOG::ptr()) };
+    let mut cmu = unsafe { Peripherals::steal_cmu() };
+    record.save(rtc, cmu.register_block());
+    error!("Resetting through watchdog");
+    let mut wdog = WdogDriver::new(wdog, &mut cmu);
+    wdog.configure_scene_and_apply(0, 0, 0, 1);
+    loop {
+        core::hint::spin_loop();
+    }
+}
@@ -0,0 +1,171 @@
+//! Handoff from the PBP to a second-stage application.
+//!
+//! The PBP fills a [`Handoff`] and jumps to the application with its address
+//! in `a0`:
+//!
+//! ```ignore
+//! let handoff = Handoff::new(boot_param, Some(reset_info.reason())).with_dram_size(dram_size);
+//! unsafe { jump_to_app(APP_ENTRY, &handoff) }
+//! ```
+//!
+//! The application reads it back through [`entry`](crate::entry):
+//!
+//! ```ignore
+//! #[entry]
+//! fn main(handoff: Option<&'static Handoff>) -> ! {
+//!     loop {}
+//! }
+//! ```
+//!
+//! The structure is made of `u32` words so any loader can fill it:
+//!
+//! | Offset | Content                                             |
+//! |:-------|:----------------------------------------------------|
+//! | 0x00   | Magic, ASCII `"AICH"`                               |
+//! | 0x04   | Version, currently 1                                |
+//! | 0x08   | Raw [`BootParam`] from the BROM                     |
+//! | 0x0C   | DRAM size in bytes, 0 if none                       |
+//! | 0x10   | PSRAM size in bytes, 0 if none                      |
+//! | 0x14   | Reset reason, 0 if unknown                          |
+//!
+//! A loader without a handoff jumps with `a0` set to 0.
+
+use super::boot_rom::{BootDevice, BootParam};
+use artinchip_hal::wri::ResetReason;
+
+/// Boot information handed from the PBP to the application.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(C)]
+pub struct Handoff {
+    magic: [u8; 4],
+    version: u32,
+    boot_param: u32,
+    dram_size: u32,
+    psram_size: u32,
+    reset_reason: u32,
+}
+
+impl Handoff {
+    const MAGIC: [u8; 4] = *b"AICH";
+    const VERSION: u32 = 1;
+
+    /// Create a handoff without DRAM or PSRAM.
+    #[inline]
+    pub const fn new(boot_param: BootParam, reset_reason: Option<ResetReason>) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            version: Self::VERSION,
+            boot_param: boot_param.as_raw(),
+            dram_size: 0,
+            psram_size: 0,
+            reset_reason: match reset_reason {
+                None => 0,
+                Some(ResetReason::PowerOnReset) => 1,
+                Some(ResetReason::RtcPowerOnReset) => 2,
+                Some(ResetReason::PinReset) => 3,
+                Some(ResetReason::CpuDebugReset) => 4,
+                Some(ResetReason::WatchdogReset) => 5,
+                Some(ResetReason::ThermalReset) => 6,
+                Some(ResetReason::ComparatorReset) => 7,
+            },
+        }
+    }
+
+    /// Set the size of the initialized DRAM in bytes.
+    #[inline]
+    pub const fn with_dram_size(mut self, size: u32) -> Self {
+        self.dram_size = size;
+        self
+    }
+
+    /// Set the size of the initialized PSRAM in bytes.
+    #[inline]
+    pub const fn with_psram_size(mut self, size: u32) -> Self {
+        self.psram_size = size;
+        self
+    }
+
+    /// Get the handoff a loader passed in `a0`, `None` if it passed none.
+    ///
+    /// # Safety
+    ///
+    /// A non-zero `arg0` must be readable memory that is not overwritten later.
+    #[inline]
+    pub unsafe fn from_arg(arg0: usize) -> Option<&'static Self> {
+        if arg0 == 0 || !arg0.is_multiple_of(align_of::<Self>()) {
+            return None;
+        }
+        let handoff = unsafe { &*(arg0 as *const Self) };
+        (handoff.magic == Self::MAGIC && handoff.version == Self::VERSION).then_some(handoff)
+    }
+
+    /// Boot parameter the BROM passed to the PBP.
+    #[inline]
+    pub const fn boot_param(&self) -> BootParam {
+        BootParam::from_raw(self.boot_param)
+    }
+
+    /// Device the PBP was booted from.
+    #[inline]
+    pub fn boot_device(&self) -> BootDevice {
+        self.boot_param().boot_device()
+    }
+
+    /// Size of the DRAM the PBP initialized, `None` if there is none.
+    #[inline]
+    pub const fn dram_size(&self) -> Option<u32> {
+        if self.dram_size == 0 {
+            None
+        } else {
+            Some(self.dram_size)
+        }
+    }
+
+    /// Size of the PSRAM the PBP initialized, `None` if there is none.
+    #[inline]
+    pub const fn psram_size(&self) -> Option<u32> {
+        if self.psram_size == 0 {
+            None
+        } else {
+            Some(self.psram_size)
+        }
+    }
+
+    /// Reset reason the PBP read, the reset flags are cleared by then.
+    ///
+    /// Encoded as 1 to 7 in the order of the [`ResetReason`] variants.
+    #[inline]
+    pub const fn reset_reason(&self) -> Option<ResetReason> {
+        match self.reset_reason {
+            1 => Some(ResetReason::PowerOnReset),
+            2 => Some(ResetReason::RtcPowerOnReset),
+            3 => Some(ResetReason::PinReset),
+            4 => Some(ResetReason::CpuDebugReset),
+            5 => Some(ResetReason::WatchdogReset),
+            6 => Some(ResetReason::ThermalReset),
+            7 => Some(ResetReason::ComparatorReset),
+            _ => None,
+        }
+    }
+}
+
+/// Jump to a second-stage application at `entry` with `handoff` in `a0`.
+///
+/// # Safety
+///
+/// The application must be loaded at `entry` and cleaned from the D-Cache
+/// (see [`dcache_clean_invalidate_range`](super::cache::dcache_clean_invalidate_range)).
+/// `handoff` must lie outside the memory of the application.
+pub unsafe fn jump_to_app(entry: usize, handoff: &Handoff) -> ! {
+    unsafe {
+        core::arch::asm!(
+            "csrci  mstatus, 0x8",
+            "fence",
+            "fence.i",
+            "jr     {entry}",
+            entry = in(reg) entry,
+            in("a0") handoff as *const Handoff,
+            options(noreturn),
+        );
+    }
+}
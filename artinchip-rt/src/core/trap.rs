@@ -1,30 +1,242 @@
 //! ArtInChip RT Trap Handler.
+//!
+//! Exceptions enter through `AlignedTrapHandler`, which saves all general
+//! purpose registers into a [`TrapFrame`] and calls [`DefaultTrapHandler`].
+//! A hook registered with [`exception`](crate::exception) gets the first
+//! chance to handle its exception, e.g. to emulate an instruction or to
+//! recover from a fault at a known probe address:
+//!
+//! ```ignore
+//! use artinchip_rt::{core::trap::TrapFrame, exception};
+//!
+//! #[exception(LoadFault)]
+//! fn probe_fault(frame: &mut TrapFrame) -> bool {
+//!     if riscv::register::mtval::read() != PROBE_ADDR {
+//!         return false; // Not ours, report it
+//!     }
+//!     frame.set_x(10, 0); // Return 0 in a0
+//!     frame.mepc += 4;
+//!     true
+//! }
+//! ```
+//!
+//! Unhandled traps are reported with a full register dump, then the
+//! [`FatalPolicy`] applies.
+//!
+//! Floating point registers are not saved, hooks must not use them.
 
+use crate::memory;
 use core::arch::global_asm;
+use core::fmt;
+use core::sync::atomic::{AtomicU8, Ordering};
 use log::error;
 use riscv::register::*;
 
-// 64-byte aligned trampoline for hardware vectoring requirement
+// 64-byte aligned trampoline for hardware vectoring requirement.
+// An overflowed stack cannot hold the trap frame, so the handler restarts
+// from the top of the stack. The frame still records the faulting `sp`,
+// and `DefaultTrapHandler` does not return to the overflowed code.
 global_asm!(
     "
+    .macro STORE_SLOT reg, num
+    .if {xlen} == 8
+        sd \\reg, ((\\num - 1) * 8)(sp)
+    .else
+        sw \\reg, ((\\num - 1) * 4)(sp)
+    .endif
+    .endm
+    .macro STORE_X num
+        STORE_SLOT x\\num, \\num
+    .endm
+    .macro LOAD_X num
+    .if {xlen} == 8
+        ld x\\num, ((\\num - 1) * 8)(sp)
+    .else
+        lw x\\num, ((\\num - 1) * 4)(sp)
+    .endif
+    .endm
+
     .align 6
     .global AlignedTrapHandler
     AlignedTrapHandler:
-        j DefaultTrapHandler
-    "
+        csrw mscratch, t0
+        la t0, _sstack
+        bgeu sp, t0, 1f
+        mv t0, sp
+        la sp, _estack
+        addi sp, sp, -{frame_size}
+        j 2f
+    1:  addi sp, sp, -{frame_size}
+        addi t0, sp, {frame_size}
+    2:  STORE_SLOT t0, 2
+        csrr t0, mscratch
+        .irp num, 1,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31
+        STORE_X \\num
+        .endr
+        csrr t0, mepc
+        .if {xlen} == 8
+        sd t0, ({frame_size} - 8)(sp)
+        .else
+        sw t0, ({frame_size} - 4)(sp)
+        .endif
+        mv a0, sp
+        call {handler}
+        .if {xlen} == 8
+        ld t0, ({frame_size} - 8)(sp)
+        .else
+        lw t0, ({frame_size} - 4)(sp)
+        .endif
+        csrw mepc, t0
+        .irp num, 1,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31
+        LOAD_X \\num
+        .endr
+        LOAD_X 2
+        mret
+    ",
+    xlen = const core::mem::size_of::<usize>(),
+    frame_size = const core::mem::size_of::<TrapFrame>(),
+    handler = sym DefaultTrapHandler,
 );
 
+/// Registers saved on trap entry.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+pub struct TrapFrame {
+    /// General purpose registers `x1` to `x31`.
+    pub regs: [usize; 31],
+    /// Address the trap returns to.
+    pub mepc: usize,
+}
+
+impl TrapFrame {
+    /// ABI names of `x0` to `x31`.
+    pub const ABI_NAMES: [&'static str; 32] = [
+        "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+        "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+        "t5", "t6",
+    ];
+
+    /// Read register `x{n}`.
+    #[inline]
+    pub fn x(&self, n: usize) -> usize {
+        if n == 0 { 0 } else { self.regs[n - 1] }
+    }
+
+    /// Write register `x{n}`, writes to `x0` are ignored.
+    #[inline]
+    pub fn set_x(&mut self, n: usize, value: usize) {
+        if n != 0 {
+            self.regs[n - 1] = value;
+        }
+    }
+}
+
+/// Four registers of a [`TrapFrame`] from `x{start}`, one line of the register dump.
+struct RegisterRow<'a>(&'a TrapFrame, usize);
+
+impl fmt::Display for RegisterRow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let RegisterRow(frame, start) = *self;
+        for n in start..(start + 4).min(32) {
+            if n != start {
+                f.write_str(" ")?;
+            }
+            write!(f, "{:>4}={:#010X}", TrapFrame::ABI_NAMES[n], frame.x(n))?;
+        }
+        Ok(())
+    }
+}
+
+macro_rules! exceptions {
+    ($($name:ident = $code:literal,)*) => {
+        /// Exceptions named after `mcause` exception codes.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #[repr(u8)]
+        pub enum Exception {
+            $($name = $code,)*
+        }
+
+        impl Exception {
+            /// Decode an `mcause` exception code.
+            #[inline]
+            pub const fn from_code(code: usize) -> Option<Self> {
+                Some(match code {
+                    $($code => Self::$name,)*
+                    _ => return None,
+                })
+            }
+
+            /// Call the user hook of this exception, returns whether it handled the trap.
+            fn call_hook(self, frame: &mut TrapFrame) -> bool {
+                paste::paste! {
+                    unsafe extern "C" {
+                        $(fn [<__exception_ $name>](frame: &mut TrapFrame) -> bool;)*
+                    }
+                    unsafe {
+                        match self {
+                            $(Self::$name => [<__exception_ $name>](frame),)*
+                        }
+                    }
+                }
+            }
+        }
+    };
+}
+artinchip_rt_macros::for_each_exception!(exceptions);
+
+/// Hook of exceptions without an `#[exception]` handler.
+#[doc(hidden)]
+#[unsafe(no_mangle)]
+pub extern "C" fn __default_exception(_frame: &mut TrapFrame) -> bool {
+    false
+}
+
+/// What the trap handler does after reporting an unhandled trap.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum FatalPolicy {
+    /// Spin forever, keeping the state for a debugger (default).
+    Halt,
+    /// Save a [`CrashRecord`](super::crash::CrashRecord) in the RTC backup
+    /// registers and reset through the watchdog. Read it back on the next
+    /// boot with [`CrashRecord::take`](super::crash::CrashRecord::take).
+    #[cfg(any(feature = "d12x", feature = "d13x", feature = "d21x", feature = "g73x"))]
+    Reset,
+}
+
+static FATAL_POLICY: AtomicU8 = AtomicU8::new(FatalPolicy::Halt as u8);
+
+/// Set what the trap handler does after reporting an unhandled trap.
+pub fn set_fatal_policy(policy: FatalPolicy) {
+    FATAL_POLICY.store(policy as u8, Ordering::Relaxed);
+}
+
 /// The default trap handler for Machine Mode (M-Mode).
 ///
 /// # Safety
 ///
-/// This function must only be invoked by the hardware trap mechanism.
-/// It relies on the `"riscv-interrupt-m"` ABI to automatically save and
-/// restore the context (registers) before and after execution.
+/// This function must only be invoked by `AlignedTrapHandler` with the
+/// registers saved in `frame`, which are restored when it returns.
 #[unsafe(no_mangle)]
-pub unsafe extern "riscv-interrupt-m" fn DefaultTrapHandler() {
+pub unsafe extern "C" fn DefaultTrapHandler(frame: &mut TrapFrame) {
     let mcause = mcause::read();
-    let mepc = mepc::read();
+    // Bits above the exception code hold CLIC state on E907
+    let code = mcause.bits() & 0xFFF;
+    let exception = if mcause.is_interrupt() {
+        None
+    } else {
+        Exception::from_code(code)
+    };
+    // A trap on an overflowed stack entered on top of the live stack,
+    // the interrupted code cannot be resumed
+    let overflowed = frame.x(2) < memory::stack_region().start;
+    if !overflowed
+        && let Some(exception) = exception
+        && exception.call_hook(frame)
+    {
+        return;
+    }
+
     let mtval = mtval::read();
     let mstatus = mstatus::read();
     let mtvec = mtvec::read();
@@ -36,19 +248,29 @@ pub unsafe extern "riscv-interrupt-m" fn DefaultTrapHandler() {
         core::arch::asm!("csrr {}, 0x7C5", out(reg) mhint);
     };
 
-    let kind = if mcause.is_interrupt() {
-        "Interrupt"
-    } else {
-        "Exception"
-    };
-
+    match exception {
+        Some(exception) => error!("TRAP: {:?} exception", exception),
+        None if mcause.is_interrupt() => error!("TRAP: Interrupt {}", code),
+        None => error!("TRAP: Unknown exception {}", code),
+    }
     error!(
-        "TRAP: {} (MCAUSE={}, MEPC={:#010X}, MTVAL={:#010X})",
-        kind,
-        mcause.code(),
-        mepc,
+        "MCAUSE={:#010X}, MEPC={:#010X}, MTVAL={:#010X}",
+        mcause.bits(),
+        frame.mepc,
         mtval
     );
+    let guard = memory::stack_guard_region();
+    if overflowed || memory::stack_overflowed() || guard.contains(&mtval) {
+        error!(
+            "Stack overflow: guard {:#010X}..{:#010X} hit, stack is {} bytes",
+            guard.start,
+            guard.end,
+            memory::stack_region().len()
+        );
+    }
+    for start in (1..32).step_by(4) {
+        error!("{}", RegisterRow(frame, start));
+    }
     error!("MSTATUS={:#010X}", mstatus.bits());
     error!("MTVEC={:#010X}", mtvec.bits());
     error!("MIP={:#010X}", mip.bits());
@@ -68,6 +290,16 @@ pub unsafe extern "riscv-interrupt-m" fn DefaultTrapHandler() {
         error!("MEXSTATUS={:#010X}", mexstatus);
     }
 
+    #[cfg(any(feature = "d12x", feature = "d13x", feature = "d21x", feature = "g73x"))]
+    if FATAL_POLICY.load(Ordering::Relaxed) == FatalPolicy::Reset as u8 {
+        super::crash::reset_with_record(&super::crash::CrashRecord {
+            interrupt: mcause.is_interrupt(),
+            code: code as u8,
+            mepc: frame.mepc as u32,
+            mtval: mtval as u32,
+            ra: frame.x(1) as u32,
+        });
+    }
     loop {
         core::hint::spin_loop();
     }
@@ -109,5 +341,14 @@ mod placeholder {
 
             riscv::interrupt::enable();
         }
+        #[cfg(feature = "d21x")]
+        unsafe {
+            // The C906 has no CLIC, set mtvec to AlignedTrapHandler in direct mode.
+            unsafe extern "C" {
+                fn AlignedTrapHandler();
+            }
+            let trap_addr = AlignedTrapHandler as *const () as usize & !0x3;
+            core::arch::asm!("csrw mtvec, {}", in(reg) trap_addr);
+        }
     }
 }
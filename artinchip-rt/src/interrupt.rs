@@ -0,0 +1,81 @@
+//! Interrupt handlers bound with `#[interrupt]`.
+//!
+//! An alternative to `clic_bind_interrupts!` for plain function handlers.
+//! The function is named after the interrupt in the chip `typelevel` list:
+//!
+//! ```ignore
+//! #![feature(abi_riscv_interrupt)]
+//!
+//! use artinchip_rt::interrupt;
+//!
+//! #[interrupt]
+//! fn GPIO_GRP_A() {
+//!     // ...
+//! }
+//! ```
+//!
+//! The macro emits the `riscv-interrupt-m` shim and a [`Vector`] entry in the
+//! `.clic.handlers` section. At startup the runtime hands those entries to the
+//! HAL, which fills its CLIC vector table and enables each bound interrupt at
+//! the highest level. A firmware binds its interrupts either with
+//! `#[interrupt]` or with `clic_bind_interrupts!`, using both fails to link.
+
+pub use artinchip_hal::interrupt::clic::typelevel;
+
+use artinchip_hal::interrupt::clic;
+use typelevel::Interrupt;
+
+/// Handler entry emitted by `#[interrupt]`.
+#[doc(hidden)]
+#[repr(C)]
+pub struct Vector {
+    irq: u8,
+    handler: extern "riscv-interrupt-m" fn(),
+}
+
+impl Vector {
+    /// Entry for interrupt `I`.
+    pub const fn new<I: Interrupt>(handler: extern "riscv-interrupt-m" fn()) -> Self {
+        Self {
+            irq: I::IRQ,
+            handler,
+        }
+    }
+}
+
+/// Handlers bound with `#[interrupt]`.
+fn vectors() -> &'static [Vector] {
+    unsafe extern "C" {
+        static __sclic_handlers: Vector;
+        static __eclic_handlers: Vector;
+    }
+    let start = &raw const __sclic_handlers;
+    let end = &raw const __eclic_handlers;
+    unsafe { core::slice::from_raw_parts(start, end.offset_from_unsigned(start)) }
+}
+
+/// Handler for interrupts without a bound handler.
+extern "riscv-interrupt-m" fn default_handler() {
+    clic::unbound_interrupt()
+}
+
+/// Fill the vector table from the `#[interrupt]` handlers and enable them.
+///
+/// Linked as `_init_vector_table` unless `clic_bind_interrupts!` defines it.
+/// Called by `_start` before the caches are enabled.
+#[unsafe(no_mangle)]
+unsafe extern "C" fn _default_init_vector_table() {
+    unsafe extern "C" {
+        fn AlignedTrapHandler();
+    }
+    let handlers = vectors()
+        .iter()
+        .map(|vector| (vector.irq, vector.handler as *const () as usize));
+    unsafe {
+        clic::init_vector_table(
+            AlignedTrapHandler as *const () as usize,
+            default_handler as *const () as usize,
+            handlers,
+        );
+    }
+}
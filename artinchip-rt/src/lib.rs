@@ -2,12 +2,28 @@
 #![no_std]
 #![feature(abi_riscv_interrupt)]
 
-pub use artinchip_rt_macros::pbp_entry;
+pub use artinchip_rt_macros::{entry, exception, interrupt, pbp_entry};
 
 #[macro_use]
 pub mod macros;
+#[cfg(any(
+    feature = "app-sram",
+    feature = "app-xip",
+    feature = "app-psram",
+    feature = "app-dram"
+))]
+pub mod app;
 pub mod core;
 pub mod gpio;
+#[cfg(feature = "interrupts")]
+pub mod interrupt;
+pub mod memory;
+#[cfg(not(any(
+    feature = "app-sram",
+    feature = "app-xip",
+    feature = "app-psram",
+    feature = "app-dram"
+)))]
 pub mod pbp;
 pub mod soc;
 
@@ -17,9 +33,23 @@ pub mod prelude {
     pub use crate::gpio::PadExt as _;
 }
 
+#[cfg(feature = "d12x")]
+pub use soc::d12x::Peripherals;
 #[cfg(feature = "d13x")]
 pub use soc::d13x::Peripherals;
+#[cfg(feature = "d21x")]
+pub use soc::d21x::Peripherals;
+#[cfg(feature = "g73x")]
+pub use soc::g73x::Peripherals;
+#[cfg(feature = "m6800")]
+pub use soc::m6800::Peripherals;
 
-#[cfg(not(feature = "d13x"))]
+#[cfg(not(any(
+    feature = "d12x",
+    feature = "d13x",
+    feature = "d21x",
+    feature = "g73x",
+    feature = "m6800"
+)))]
 /// Mock peripheral struct for unselected chips.
 pub struct Peripherals {}
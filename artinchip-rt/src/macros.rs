@@ -2,3 +2,5 @@
 mod soc;
 #[macro_use]
 mod pin_mux;
+#[macro_use]
+mod hart;
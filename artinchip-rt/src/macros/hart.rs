@@ -0,0 +1,54 @@
+//! Hart specific parts of `_start`.
+//!
+//! T-Head CSRs are not known to the assembler, so they are given by number:
+//! `mxstatus` (0x7c0), `mcor` (0x7c2), `mhint` (0x7c5) and `mexstatus` (0x7e1).
+
+/// E907: enable T-Head instruction sets (THEADISAEE) and misaligned access
+/// (MM) in `mxstatus`. Caches (MHCR/MHINT) are enabled later by `_enable_cache`.
+// TODO SPUSHEN and SPSWAPEN in `mexstatus` once we have trap handler
+#[cfg(not(feature = "d21x"))]
+macro_rules! hart_init {
+    () => {
+        "   li      t2, 0x408000
+            csrs    0x7c0, t2"
+    };
+}
+
+/// C906: invalidate I-Cache, D-Cache, BHT and BTB in `mcor`, which are not
+/// cleared on reset, then enable T-Head instruction sets (THEADISAEE) and
+/// misaligned access (MM) in `mxstatus` and the D-Cache and I-Cache
+/// prefetch in `mhint`. Caches (MHCR) are enabled later by `_enable_cache`.
+#[cfg(feature = "d21x")]
+macro_rules! hart_init {
+    () => {
+        "   li      t0, 0x70013
+            csrw    0x7c2, t0
+            li      t2, 0x408000
+            csrs    0x7c0, t2
+            li      t0, 0x16e30c
+            csrw    0x7c5, t0"
+    };
+}
+
+/// E907: set wfi behavior to deep-sleep by clearing LPMD and WFEEN in
+/// `mexstatus`, disable interrupt then loop-wfi.
+#[cfg(not(feature = "d21x"))]
+macro_rules! hart_halt {
+    () => {
+        "   li      t0, 0x1c
+            csrc    0x7e1, t0
+            csrci   mstatus, 0x8
+        3:  wfi
+            j       3b"
+    };
+}
+
+/// C906: disable interrupt then loop-wfi, it has no `mexstatus`.
+#[cfg(feature = "d21x")]
+macro_rules! hart_halt {
+    () => {
+        "   csrci   mstatus, 0x8
+        3:  wfi
+            j       3b"
+    };
+}
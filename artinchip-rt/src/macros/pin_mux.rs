@@ -14,7 +14,7 @@ macro_rules! gpio {
 
             impl $Pads {
                 #[inline]
-                const fn __new() -> Self {
+                pub(crate) const fn __new() -> Self {
                     Self {
                         $(
                             [<p $G:lower $N>]: unsafe {crate::gpio::GpioPad::__new(&*GPIO::ptr())},
@@ -68,6 +68,46 @@ macro_rules! uart_rx {
     };
 }
 
+/// Implements the `Rts` and `UartPad` traits for multiple UART pins.
+#[allow(unused_macros)]
+macro_rules! uart_rts {
+    ($uart_num:expr, $(($port:literal, $pin:expr, $func:expr)),+) => {
+        $(
+            impl artinchip_hal::uart::UartPad<$uart_num> for crate::gpio::Function<'_, $port, $pin, $func> {}
+            impl artinchip_hal::uart::Rts<$uart_num> for crate::gpio::Function<'_, $port, $pin, $func> {}
+
+            paste! {
+                impl<'a> crate::gpio::GpioPad<$port, $pin> {
+                    #[inline]
+                    pub fn [<into_uart $uart_num _rts>](self) -> crate::gpio::Function<'a, $port, $pin, $func> {
+                        self.into_function::<$func>()
+                    }
+                }
+            }
+        )+
+    };
+}
+
+/// Implements the `Cts` and `UartPad` traits for multiple UART pins.
+#[allow(unused_macros)]
+macro_rules! uart_cts {
+    ($uart_num:expr, $(($port:literal, $pin:expr, $func:expr)),+) => {
+        $(
+            impl artinchip_hal::uart::UartPad<$uart_num> for crate::gpio::Function<'_, $port, $pin, $func> {}
+            impl artinchip_hal::uart::Cts<$uart_num> for crate::gpio::Function<'_, $port, $pin, $func> {}
+
+            paste! {
+                impl<'a> crate::gpio::GpioPad<$port, $pin> {
+                    #[inline]
+                    pub fn [<into_uart $uart_num _cts>](self) -> crate::gpio::Function<'a, $port, $pin, $func> {
+                        self.into_function::<$func>()
+                    }
+                }
+            }
+        )+
+    };
+}
+
 // QSPI pin multiplexer macros.
 
 /// Implements the `SerialClock` traits for multiple QSPI pins.
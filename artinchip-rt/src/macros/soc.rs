@@ -34,3 +34,67 @@ macro_rules! soc {
         )+
     };
 }
+
+macro_rules! peripherals {
+    (
+        $(#[$doc:meta])*
+        pub struct Peripherals {
+            $(
+                $(#[$field_doc:meta])*
+                pub $field:ident: $Ty:ty = $init:expr,
+            )+
+        }
+    ) => {
+        $(#[$doc])*
+        pub struct Peripherals {
+            $(
+                $(#[$field_doc])*
+                pub $field: $Ty,
+            )+
+        }
+
+        static TAKEN: artinchip_hal::types::TakeOnce = artinchip_hal::types::TakeOnce::new();
+
+        impl Peripherals {
+            #[doc(hidden)]
+            #[inline]
+            pub const fn __new() -> Self {
+                Self {
+                    $($field: $init,)+
+                }
+            }
+
+            /// Take the peripherals, returns `None` if they have already been taken.
+            #[inline]
+            pub fn take() -> Option<Self> {
+                TAKEN.take().then(Self::__new)
+            }
+
+            /// Steal the peripherals, whether or not they have been taken.
+            ///
+            /// # Safety
+            ///
+            /// The caller must not use the stolen peripherals concurrently with their
+            /// owners, e.g. only in a panic or trap handler that does not return.
+            #[inline]
+            pub unsafe fn steal() -> Self {
+                TAKEN.take();
+                Self::__new()
+            }
+
+            paste::paste! {
+                $(
+                    /// Steal a single peripheral, whether or not it has been taken.
+                    ///
+                    /// # Safety
+                    ///
+                    /// See [`Peripherals::steal`].
+                    #[inline]
+                    pub unsafe fn [<steal_ $field>]() -> $Ty {
+                        $init
+                    }
+                )+
+            }
+        }
+    };
+}
@@ -0,0 +1,114 @@
+//! Memory regions reserved by the linker script.
+//!
+//! Stack and heap sizes default to 2 KiB and 0 for a PBP, 4 KiB and 0 for an
+//! application. They are overridden in the `memory.x` of the downstream crate
+//! (enable the `memory-x` feature), next to its regions:
+//!
+//! ```text
+//! MEMORY {
+//!     SRAM : ORIGIN = 0x30044000 - 0x8, LENGTH = 64K + 0x8
+//! }
+//! REGION_ALIAS("REGION_TEXT", SRAM);
+//! REGION_ALIAS("REGION_DATA", SRAM);
+//!
+//! _stack_size = 8K;
+//! _heap_size = 16K;
+//! ```
+//!
+//! The downstream `build.rs` copies `memory.x` into `OUT_DIR` and adds it to
+//! the linker search path with `cargo:rustc-link-search`.
+//!
+//! # Stack overflow detection
+//!
+//! `_start` paints the stack and a [`STACK_GUARD_SIZE`] guard below it with
+//! [`STACK_PAINT`]. [`stack_high_water_mark`] measures the stack usage from
+//! the paint, and the trap handler reports a stack overflow when the guard
+//! has been overwritten.
+//!
+//! With the `stack-guard` feature the guard is also protected by a locked
+//! PMP entry, so an overflow traps on the first store into the guard. The
+//! entry stays locked until reset, so only enable it in the last boot stage.
+
+use core::ops::Range;
+use core::ptr;
+
+/// Word `_start` paints the stack and the guard with.
+pub const STACK_PAINT: u32 = 0x5A5A_5A5A;
+/// Size of the guard below the stack.
+#[cfg(not(all(feature = "d21x", feature = "stack-guard")))]
+pub const STACK_GUARD_SIZE: usize = 64;
+/// Size of the guard below the stack.
+///
+/// The C906 PMP has a 4 KiB granularity, so the protected guard is a whole,
+/// 4 KiB aligned granule.
+#[cfg(all(feature = "d21x", feature = "stack-guard"))]
+pub const STACK_GUARD_SIZE: usize = 4096;
+
+unsafe extern "C" {
+    static _sguard: u8;
+    static _sstack: u8;
+    static _estack: u8;
+    static _sheap: u8;
+    static _eheap: u8;
+}
+
+/// Address range of the stack, which grows down from `end`.
+#[inline]
+pub fn stack_region() -> Range<usize> {
+    (&raw const _sstack as usize)..(&raw const _estack as usize)
+}
+
+/// Address range of the guard below the stack.
+#[inline]
+pub fn stack_guard_region() -> Range<usize> {
+    (&raw const _sguard as usize)..(&raw const _sstack as usize)
+}
+
+/// Maximum stack usage in bytes since reset.
+///
+/// Returns the full stack size once the stack has overflowed.
+pub fn stack_high_water_mark() -> usize {
+    let stack = stack_region();
+    let unused = stack
+        .clone()
+        .step_by(4)
+        .take_while(|&addr| unsafe { ptr::read_volatile(addr as *const u32) } == STACK_PAINT)
+        .count();
+    stack.len() - unused * 4
+}
+
+/// Whether the guard below the stack has been overwritten.
+pub fn stack_overflowed() -> bool {
+    stack_guard_region()
+        .step_by(4)
+        .any(|addr| unsafe { ptr::read_volatile(addr as *const u32) } != STACK_PAINT)
+}
+
+/// Protect the stack guard with a locked PMP entry.
+///
+/// Called by `_start` right after the stack is set up.
+#[unsafe(no_mangle)]
+pub(crate) extern "C" fn _init_stack_guard() {
+    #[cfg(feature = "stack-guard")]
+    {
+        // PMP entry 0 (OFF) holds the guard base for the TOR entry 1,
+        // which is locked without any permission so M-mode is checked too.
+        // The linker script aligns both ends to the PMP granularity.
+        const PMP1_TOR_LOCKED: usize = (0x80 | 0x08) << 8;
+        let guard = stack_guard_region();
+        unsafe {
+            core::arch::asm!("csrw pmpaddr0, {}", in(reg) guard.start >> 2);
+            core::arch::asm!("csrw pmpaddr1, {}", in(reg) guard.end >> 2);
+            core::arch::asm!("csrs pmpcfg0, {}", in(reg) PMP1_TOR_LOCKED);
+        }
+    }
+}
+
+/// Address range of the heap.
+///
+/// The runtime does not manage the heap; hand it to an allocator
+/// such as `embedded-alloc`.
+#[inline]
+pub fn heap_region() -> Range<usize> {
+    (&raw const _sheap as usize)..(&raw const _eheap as usize)
+}
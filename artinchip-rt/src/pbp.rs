@@ -1,4 +1,5 @@
 //! Pre-Boot Program runtime.
+use crate::memory::STACK_PAINT;
 use core::arch::naked_asm;
 
 /// Pre-Boot Program header structure.
@@ -19,28 +20,19 @@ pub static PBP_HEADER: PbpHeader = PbpHeader {
     checksum: 0x0, // <- Real checksum filled by PBP tools.
 };
 
-#[unsafe(link_section = ".bss.uninit")]
-static mut STACK: [u8; STACK_SIZE] = [0u8; STACK_SIZE];
-const STACK_SIZE: usize = 2048; // 2 KiB
-
-const MXSTATUS: u16 = 0x7c0;
-const MEXSTATUS: u16 = 0x7e1;
-
 #[unsafe(naked)]
 #[unsafe(no_mangle)]
 #[unsafe(link_section = ".text.entry")]
 pub extern "C" fn _start() {
     naked_asm!(
-        // 1. Disable interrupt
-        "   csrw    mie, zero",
+        // 1. Disable interrupt and keep the BROM arguments
+        "   csrw    mie, zero
+            mv      s0, a0
+            mv      s1, a1
+            mv      s2, a2",
 
-        // 2. Hart specific initialization
-        // - Enable T-Head instruction sets (THEADISAEE) and
-        // misaligned access (MM) in `mxstatus` register.
-        // Cache (MHCR/MHINT) enabled later via enable_cache().
-        // TODO SPUSHEN and SPSWAPEN in `mexstatus` once we have trap handler
-        "   li      t2, 0x408000
-            csrs    {mxstatus}, t2",
+        // 2. Hart specific initialization, see `hart_init!`
+        hart_init!(),
 
         // 3. Initialize float point unit
         "   li      t0, 0x4000
@@ -49,46 +41,65 @@ pub extern "C" fn _start() {
             csrs    mstatus, t1
             csrw    fcsr, zero",
 
-        // 4. Clear `.bss` section
-        "   la      t0, sbss
+        // 4. Copy `.data` section from its load address
+        // The BROM loads a PBP at its run address, so this is normally skipped.
+        "   la      t0, sdata
+            la      t1, edata
+            la      t2, sidata
+            beq     t0, t2, 2f
+        1:  bgeu    t0, t1, 2f
+            lw      t3, 0(t2)
+            sw      t3, 0(t0)
+            addi    t0, t0, 4
+            addi    t2, t2, 4
+            j       1b",
+
+        // 5. Clear `.bss` section
+        "2: la      t0, sbss
             la      t1, ebss
         1:  bgeu    t0, t1, 2f
             sw      zero, 0(t0)
             addi    t0, t0, 4
             j       1b",
 
-        // 5. Prepare programming language stack
-        "2: la      sp, {stack} + {stack_size}",
+        // 6. Paint guard and stack to detect overflows and measure usage
+        "2: la      t0, _sguard
+            la      t1, _estack
+            li      t2, {paint}
+        1:  bgeu    t0, t1, 2f
+            sw      t2, 0(t0)
+            addi    t0, t0, 4
+            j       1b",
+
+        // 7. Prepare programming language stack
+        "2: la      sp, _estack",
 
-        // 6. Init vector table and enable caches before main
+        // 8. Init stack guard and vector table, enable caches before main
+        "   call    {init_stack_guard}",
         "   call    {init_vector_table}",
         "   call    {enable_cache}",
         "   fence.i",
 
-        // 7. Start Rust main function
-        "   j       {main}",
+        // 9. Start Rust main function
+        "   mv      a0, s0
+            mv      a1, s1
+            mv      a2, s2
+            j       {main}",
 
-        // 8. Platform halt (by loop-wfi) if main function returns
-        // Set T-Head wfi behavior to deep-sleep, disable interrupt then
-        // loop-wfi. Clears LPMD=0 and WFEEN=0 in `mexstatus`.
-        "   li      t0, 0x1c
-            csrc    {mexstatus}, t0
-            csrci   mstatus, 0x8
-        3:  wfi
-            j       3b",
+        // 10. Platform halt (by loop-wfi) if main function returns
+        hart_halt!(),
 
-        stack_size       = const STACK_SIZE,
-        stack            =   sym STACK,
         main             =   sym pbp_main,
+        init_stack_guard =   sym _init_stack_guard,
         init_vector_table =  sym _init_vector_table,
         enable_cache     =   sym _enable_cache,
-        mxstatus         =   const MXSTATUS,
-        mexstatus        =   const MEXSTATUS,
+        paint            =   const STACK_PAINT,
     )
 }
 
 unsafe extern "C" {
     unsafe fn pbp_main(boot_param: u32, priv_addr: *const u8, priv_len: u32);
+    unsafe fn _init_stack_guard();
     unsafe fn _init_vector_table();
     unsafe fn _enable_cache();
 }
@@ -1,3 +1,17 @@
 //! ArtInChip SoC periphral support module.
 
+#[cfg(feature = "d12x")]
+pub mod d12x;
+#[cfg(any(
+    feature = "d12x",
+    feature = "d13x",
+    feature = "g73x",
+    feature = "m6800"
+))]
 pub mod d13x;
+#[cfg(feature = "d21x")]
+pub mod d21x;
+#[cfg(feature = "g73x")]
+pub mod g73x;
+#[cfg(feature = "m6800")]
+pub mod m6800;
@@ -0,0 +1,79 @@
+//! ArtInChip D12x chip series.
+//!
+//! The D12x is a reduced D13x, with UART0 to UART3, I2C0 and I2C1, QSPI0 and
+//! QSPI1 and no Crypto Engine. Its CAN0 and CAN1 have no HAL driver yet.
+//!
+//! The memory map, GPIO groups A to E and the pin mux of those peripherals
+//! are shared with [`d13x`](super::d13x).
+
+use artinchip_hal::instances::*;
+
+pub use super::d13x::{
+    AXICFG, CLIC, CLINT, CMU, DMA, GPIO, GTC, GpioAPads, GpioBPads, GpioCPads, GpioDPads,
+    GpioEPads, I2C0, I2C1, PWM, QSPI0, QSPI1, RTC, SDMC0, SDMC1, SID, SPI_ENC, SYSCFG, UART0,
+    UART1, UART2, UART3, WDOG, WRI, XSPI,
+};
+
+peripherals! {
+    /// ArtInChip D12x peripheral ownership structures available on BootROM.
+    pub struct Peripherals {
+        /// Direct Memory Access.
+        pub dma: Dma = Dma::__new(DMA::ptr()),
+        /// Expanded Serial Peripheral Interface.
+        pub xspi: Xspi = Xspi::__new(XSPI::ptr()),
+        /// Quad Serial Peripheral Interface 0.
+        pub qspi0: Qspi<0> = Qspi::__new(QSPI0::ptr()),
+        /// Quad Serial Peripheral Interface 1.
+        pub qspi1: Qspi<1> = Qspi::__new(QSPI1::ptr()),
+        /// Secure Digital Host Controller 0.
+        pub sdmc0: Sdmc<0> = Sdmc::__new(SDMC0::ptr()),
+        /// Secure Digital Host Controller 1.
+        pub sdmc1: Sdmc<1> = Sdmc::__new(SDMC1::ptr()),
+        /// System Configuration.
+        pub syscfg: SysCfg = SysCfg::__new(SYSCFG::ptr()),
+        /// Clock Manage Unit.
+        pub cmu: Cmu = Cmu::__new(CMU::ptr()),
+        /// Serial Peripheral Interface Encryption.
+        pub spi_enc: SpiEnc = SpiEnc::__new(SPI_ENC::ptr()),
+        /// AXI Configuration.
+        pub axi_cfg: AxiCfg = AxiCfg::__new(AXICFG::ptr()),
+        /// General Purpose Input/Output group A.
+        pub gpioa: GpioAPads = GpioAPads::__new(),
+        /// General Purpose Input/Output group B.
+        pub gpiob: GpioBPads = GpioBPads::__new(),
+        /// General Purpose Input/Output group C.
+        pub gpioc: GpioCPads = GpioCPads::__new(),
+        /// General Purpose Input/Output group D.
+        pub gpiod: GpioDPads = GpioDPads::__new(),
+        /// General Purpose Input/Output group E.
+        pub gpioe: GpioEPads = GpioEPads::__new(),
+        /// Universal Asynchronous Receiver-Transmitter 0.
+        pub uart0: Uart<0> = Uart::__new(UART0::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 1.
+        pub uart1: Uart<1> = Uart::__new(UART1::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 2.
+        pub uart2: Uart<2> = Uart::__new(UART2::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 3.
+        pub uart3: Uart<3> = Uart::__new(UART3::ptr()),
+        /// Watch Dog Timer.
+        pub wdog: Wdog = Wdog::__new(WDOG::ptr()),
+        /// Warm Reset Information.
+        pub wri: Wri = Wri::__new(WRI::ptr()),
+        /// Secure ID.
+        pub sid: Sid = Sid::__new(SID::ptr()),
+        /// Real Time Clock.
+        pub rtc: Rtc = Rtc::__new(RTC::ptr()),
+        /// Generic Timer Controller.
+        pub gtc: Gtc = Gtc::__new(GTC::ptr()),
+        /// Inter-Integrated Circuit 0.
+        pub i2c0: I2c<0> = I2c::__new(I2C0::ptr()),
+        /// Inter-Integrated Circuit 1.
+        pub i2c1: I2c<1> = I2c::__new(I2C1::ptr()),
+        /// Pulse Width Modulation.
+        pub pwm: PwmChannels = PwmChannels::__new(PWM::ptr()),
+        /// Core Local Interrupt.
+        pub clint: Clint = Clint::__new(CLINT::ptr()),
+        /// Core Local Interrupt Controller.
+        pub clic: Clic = Clic::__new(CLIC::ptr()),
+    }
+}
@@ -0,0 +1,195 @@
+//! ArtInChip D21x chip series.
+//!
+//! The D21x runs a 64-bit C906 hart with a CLINT and a PLIC instead of the
+//! E907 CLIC, so interrupts are not bound through the CLIC vector table.
+
+use crate::gpio::PadExt as _;
+use artinchip_hal::instances::*;
+use paste::paste;
+
+peripherals! {
+    /// ArtInChip D21x peripheral ownership structures available on BootROM.
+    pub struct Peripherals {
+        /// Direct Memory Access.
+        pub dma: Dma = Dma::__new(DMA::ptr()),
+        /// Crypto Engine.
+        pub ce: Ce = Ce::__new(CE::ptr()),
+        /// Quad Serial Peripheral Interface 0.
+        pub qspi0: Qspi<0> = Qspi::__new(QSPI0::ptr()),
+        /// Quad Serial Peripheral Interface 1.
+        pub qspi1: Qspi<1> = Qspi::__new(QSPI1::ptr()),
+        /// Quad Serial Peripheral Interface 2.
+        pub qspi2: Qspi<2> = Qspi::__new(QSPI2::ptr()),
+        /// Quad Serial Peripheral Interface 3.
+        pub qspi3: Qspi<3> = Qspi::__new(QSPI3::ptr()),
+        /// Secure Digital Host Controller 0.
+        pub sdmc0: Sdmc<0> = Sdmc::__new(SDMC0::ptr()),
+        /// Secure Digital Host Controller 1.
+        pub sdmc1: Sdmc<1> = Sdmc::__new(SDMC1::ptr()),
+        /// Secure Digital Host Controller 2.
+        pub sdmc2: Sdmc<2> = Sdmc::__new(SDMC2::ptr()),
+        /// System Configuration.
+        pub syscfg: SysCfg = SysCfg::__new(SYSCFG::ptr()),
+        /// Clock Manage Unit.
+        pub cmu: Cmu = Cmu::__new(CMU::ptr()),
+        /// Serial Peripheral Interface Encryption.
+        pub spi_enc: SpiEnc = SpiEnc::__new(SPI_ENC::ptr()),
+        /// General Purpose Input/Output group A.
+        pub gpioa: GpioAPads = GpioAPads::__new(),
+        /// General Purpose Input/Output group B.
+        pub gpiob: GpioBPads = GpioBPads::__new(),
+        /// General Purpose Input/Output group C.
+        pub gpioc: GpioCPads = GpioCPads::__new(),
+        /// General Purpose Input/Output group D.
+        pub gpiod: GpioDPads = GpioDPads::__new(),
+        /// General Purpose Input/Output group E.
+        pub gpioe: GpioEPads = GpioEPads::__new(),
+        /// General Purpose Input/Output group F.
+        pub gpiof: GpioFPads = GpioFPads::__new(),
+        /// Universal Asynchronous Receiver-Transmitter 0.
+        pub uart0: Uart<0> = Uart::__new(UART0::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 1.
+        pub uart1: Uart<1> = Uart::__new(UART1::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 2.
+        pub uart2: Uart<2> = Uart::__new(UART2::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 3.
+        pub uart3: Uart<3> = Uart::__new(UART3::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 4.
+        pub uart4: Uart<4> = Uart::__new(UART4::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 5.
+        pub uart5: Uart<5> = Uart::__new(UART5::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 6.
+        pub uart6: Uart<6> = Uart::__new(UART6::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 7.
+        pub uart7: Uart<7> = Uart::__new(UART7::ptr()),
+        /// Watch Dog Timer.
+        pub wdog: Wdog = Wdog::__new(WDOG::ptr()),
+        /// Warm Reset Information.
+        pub wri: Wri = Wri::__new(WRI::ptr()),
+        /// Secure ID.
+        pub sid: Sid = Sid::__new(SID::ptr()),
+        /// Real Time Clock.
+        pub rtc: Rtc = Rtc::__new(RTC::ptr()),
+        /// Generic Timer Controller.
+        pub gtc: Gtc = Gtc::__new(GTC::ptr()),
+        /// Inter-Integrated Circuit 0.
+        pub i2c0: I2c<0> = I2c::__new(I2C0::ptr()),
+        /// Inter-Integrated Circuit 1.
+        pub i2c1: I2c<1> = I2c::__new(I2C1::ptr()),
+        /// Inter-Integrated Circuit 2.
+        pub i2c2: I2c<2> = I2c::__new(I2C2::ptr()),
+        /// Inter-Integrated Circuit 3.
+        pub i2c3: I2c<3> = I2c::__new(I2C3::ptr()),
+        /// Pulse Width Modulation.
+        pub pwm: PwmChannels = PwmChannels::__new(PWM::ptr()),
+        /// Core Local Interrupt.
+        pub clint: Clint = Clint::__new(CLINT::ptr()),
+    }
+}
+
+soc! {
+    /// Direct Memory Access.
+    pub struct DMA => 0x10000000, artinchip_hal::dma::RegisterBlock;
+    /// Crypto Engine.
+    pub struct CE => 0x10020000, artinchip_hal::ce::RegisterBlock;
+    /// Quad Serial Peripheral Interface 0.
+    pub struct QSPI0 => 0x10400000, artinchip_hal::qspi::RegisterBlock;
+    /// Quad Serial Peripheral Interface 1.
+    pub struct QSPI1 => 0x10410000, artinchip_hal::qspi::RegisterBlock;
+    /// Quad Serial Peripheral Interface 2.
+    pub struct QSPI2 => 0x10420000, artinchip_hal::qspi::RegisterBlock;
+    /// Quad Serial Peripheral Interface 3.
+    pub struct QSPI3 => 0x10430000, artinchip_hal::qspi::RegisterBlock;
+    /// Secure Digital Host Controller 0.
+    pub struct SDMC0 => 0x10440000, artinchip_hal::sdmc::RegisterBlock;
+    /// Secure Digital Host Controller 1.
+    pub struct SDMC1 => 0x10450000, artinchip_hal::sdmc::RegisterBlock;
+    /// Secure Digital Host Controller 2.
+    pub struct SDMC2 => 0x10460000, artinchip_hal::sdmc::RegisterBlock;
+    /// System Configuration.
+    pub struct SYSCFG => 0x18000000, artinchip_hal::sys_cfg::RegisterBlock;
+    /// Clock Manage Unit.
+    pub struct CMU => 0x18020000, artinchip_hal::cmu::RegisterBlock;
+    /// Serial Peripheral Interface Encryption.
+    pub struct SPI_ENC => 0x18100000, artinchip_hal::spi_enc::RegisterBlock;
+    /// General Purpose Input/Output.
+    pub struct GPIO => 0x18700000, artinchip_hal::gpio::RegisterBlock;
+    /// Universal Asynchronous Receiver-Transmitter 0.
+    pub struct UART0 => 0x18710000, artinchip_hal::uart::RegisterBlock;
+    /// Universal Asynchronous Receiver-Transmitter 1.
+    pub struct UART1 => 0x18711000, artinchip_hal::uart::RegisterBlock;
+    /// Universal Asynchronous Receiver-Transmitter 2.
+    pub struct UART2 => 0x18712000, artinchip_hal::uart::RegisterBlock;
+    /// Universal Asynchronous Receiver-Transmitter 3.
+    pub struct UART3 => 0x18713000, artinchip_hal::uart::RegisterBlock;
+    /// Universal Asynchronous Receiver-Transmitter 4.
+    pub struct UART4 => 0x18714000, artinchip_hal::uart::RegisterBlock;
+    /// Universal Asynchronous Receiver-Transmitter 5.
+    pub struct UART5 => 0x18715000, artinchip_hal::uart::RegisterBlock;
+    /// Universal Asynchronous Receiver-Transmitter 6.
+    pub struct UART6 => 0x18716000, artinchip_hal::uart::RegisterBlock;
+    /// Universal Asynchronous Receiver-Transmitter 7.
+    pub struct UART7 => 0x18717000, artinchip_hal::uart::RegisterBlock;
+    /// Watch Dog Timer.
+    pub struct WDOG => 0x19000000, artinchip_hal::wdog::RegisterBlock;
+    /// Warm Reset Information.
+    pub struct WRI => 0x1900F000, artinchip_hal::wri::RegisterBlock;
+    /// Secure ID.
+    pub struct SID => 0x19010000, artinchip_hal::sid::RegisterBlock;
+    /// Real Time Clock.
+    pub struct RTC => 0x19030000, artinchip_hal::rtc::RegisterBlock;
+    /// Generic Timer Controller.
+    pub struct GTC => 0x19050000, artinchip_hal::gtc::RegisterBlock;
+    /// Inter-Integrated Circuit 0.
+    pub struct I2C0 => 0x19220000, artinchip_hal::i2c::RegisterBlock;
+    /// Inter-Integrated Circuit 1.
+    pub struct I2C1 => 0x19221000, artinchip_hal::i2c::RegisterBlock;
+    /// Inter-Integrated Circuit 2.
+    pub struct I2C2 => 0x19222000, artinchip_hal::i2c::RegisterBlock;
+    /// Inter-Integrated Circuit 3.
+    pub struct I2C3 => 0x19223000, artinchip_hal::i2c::RegisterBlock;
+    /// Pulse Width Modulation.
+    pub struct PWM => 0x19240000, artinchip_hal::pwm::RegisterBlock;
+    /// Core Local Interrupt.
+    pub struct CLINT => 0x74000000, artinchip_hal::clint::RegisterBlock;
+}
+
+// GPIO Pads
+#[rustfmt::skip]
+gpio!(GpioAPads, 'A', [0,1,2,3,4,5,6,7,8,9,10,11]);
+#[rustfmt::skip]
+gpio!(GpioBPads, 'B', [0,1,2,3,4,5,6,7,8,9,10,11]);
+#[rustfmt::skip]
+gpio!(GpioCPads, 'C', [0,1,2,3,4,5,6,7,8,9,10,11]);
+#[rustfmt::skip]
+gpio!(GpioDPads, 'D', [0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27]);
+#[rustfmt::skip]
+gpio!(GpioEPads, 'E', [0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19]);
+#[rustfmt::skip]
+gpio!(GpioFPads, 'F', [0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15]);
+
+// QSPI pin mux for D21x series.
+qspi_sck!(0, ('B', 4, 2)); // QSPI0
+qspi_mosi!(0, ('B', 5, 2));
+qspi_miso!(0, ('B', 1, 2));
+qspi_cs!(0, ('B', 2, 2));
+qspi_hold!(0, ('B', 3, 2));
+qspi_wp!(0, ('B', 0, 2));
+
+// UART pin mux for D21x series.
+uart_tx!(0, ('A', 0, 5), ('D', 0, 5)); // UART0
+uart_rx!(0, ('A', 1, 5), ('D', 1, 5));
+uart_tx!(1, ('A', 2, 5), ('D', 2, 5)); // UART1
+uart_rx!(1, ('A', 3, 5), ('D', 3, 5));
+uart_tx!(2, ('A', 4, 5), ('D', 4, 5)); // UART2
+uart_rx!(2, ('A', 5, 5), ('D', 5, 5));
+uart_tx!(3, ('A', 6, 5), ('D', 6, 5)); // UART3
+uart_rx!(3, ('A', 7, 5), ('D', 7, 5));
+
+// I2C pin mux for D21x series.
+i2c_scl!(0, ('A', 0, 4), ('D', 0, 4)); // I2C0
+i2c_sda!(0, ('A', 1, 4), ('D', 1, 4));
+i2c_scl!(1, ('A', 2, 4), ('D', 2, 4)); // I2C1
+i2c_sda!(1, ('A', 3, 4), ('D', 3, 4));
+i2c_scl!(2, ('A', 8, 4), ('D', 4, 4)); // I2C2
+i2c_sda!(2, ('A', 9, 4), ('D', 5, 4));
@@ -0,0 +1,96 @@
+//! ArtInChip G73x chip series.
+//!
+//! The G73x motor control chips share the D13x memory map and GPIO groups
+//! A to E, re-exported from [`d13x`](super::d13x). Their motor control
+//! peripherals (PWMCS, SDFM, CORDIC, ...) have no HAL driver yet.
+//!
+//! There are no pin mux entries yet, they are added once checked against the
+//! G73x pin list. Until then select pad functions with `into_function`.
+
+use artinchip_hal::instances::*;
+
+pub use super::d13x::{
+    AXICFG, CE, CLIC, CLINT, CMU, DMA, GPIO, GTC, GpioAPads, GpioBPads, GpioCPads, GpioDPads,
+    GpioEPads, I2C0, I2C1, I2C2, PWM, QSPI0, QSPI1, QSPI2, QSPI3, RTC, SDMC0, SDMC1, SID, SPI_ENC,
+    SYSCFG, UART0, UART1, UART2, UART3, UART4, UART5, UART6, UART7, WDOG, WRI, XSPI,
+};
+
+peripherals! {
+    /// ArtInChip G73x peripheral ownership structures available on BootROM.
+    pub struct Peripherals {
+        /// Direct Memory Access.
+        pub dma: Dma = Dma::__new(DMA::ptr()),
+        /// Crypto Engine.
+        pub ce: Ce = Ce::__new(CE::ptr()),
+        /// Expanded Serial Peripheral Interface.
+        pub xspi: Xspi = Xspi::__new(XSPI::ptr()),
+        /// Quad Serial Peripheral Interface 0.
+        pub qspi0: Qspi<0> = Qspi::__new(QSPI0::ptr()),
+        /// Quad Serial Peripheral Interface 1.
+        pub qspi1: Qspi<1> = Qspi::__new(QSPI1::ptr()),
+        /// Quad Serial Peripheral Interface 2.
+        pub qspi2: Qspi<2> = Qspi::__new(QSPI2::ptr()),
+        /// Quad Serial Peripheral Interface 3.
+        pub qspi3: Qspi<3> = Qspi::__new(QSPI3::ptr()),
+        /// Secure Digital Host Controller 0.
+        pub sdmc0: Sdmc<0> = Sdmc::__new(SDMC0::ptr()),
+        /// Secure Digital Host Controller 1.
+        pub sdmc1: Sdmc<1> = Sdmc::__new(SDMC1::ptr()),
+        /// System Configuration.
+        pub syscfg: SysCfg = SysCfg::__new(SYSCFG::ptr()),
+        /// Clock Manage Unit.
+        pub cmu: Cmu = Cmu::__new(CMU::ptr()),
+        /// Serial Peripheral Interface Encryption.
+        pub spi_enc: SpiEnc = SpiEnc::__new(SPI_ENC::ptr()),
+        /// AXI Configuration.
+        pub axi_cfg: AxiCfg = AxiCfg::__new(AXICFG::ptr()),
+        /// General Purpose Input/Output group A.
+        pub gpioa: GpioAPads = GpioAPads::__new(),
+        /// General Purpose Input/Output group B.
+        pub gpiob: GpioBPads = GpioBPads::__new(),
+        /// General Purpose Input/Output group C.
+        pub gpioc: GpioCPads = GpioCPads::__new(),
+        /// General Purpose Input/Output group D.
+        pub gpiod: GpioDPads = GpioDPads::__new(),
+        /// General Purpose Input/Output group E.
+        pub gpioe: GpioEPads = GpioEPads::__new(),
+        /// Universal Asynchronous Receiver-Transmitter 0.
+        pub uart0: Uart<0> = Uart::__new(UART0::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 1.
+        pub uart1: Uart<1> = Uart::__new(UART1::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 2.
+        pub uart2: Uart<2> = Uart::__new(UART2::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 3.
+        pub uart3: Uart<3> = Uart::__new(UART3::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 4.
+        pub uart4: Uart<4> = Uart::__new(UART4::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 5.
+        pub uart5: Uart<5> = Uart::__new(UART5::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 6.
+        pub uart6: Uart<6> = Uart::__new(UART6::ptr()),
+        /// Universal Asynchronous Receiver-Transmitter 7.
+        pub uart7: Uart<7> = Uart::__new(UART7::ptr()),
+        /// Watch Dog Timer.
+        pub wdog: Wdog = Wdog::__new(WDOG::ptr()),
+        /// Warm Reset Information.
+        pub wri: Wri = Wri::__new(WRI::ptr()),
+        /// Secure ID.
+        pub sid: Sid = Sid::__new(SID::ptr()),
+        /// Real Time Clock.
+        pub rtc: Rtc = Rtc::__new(RTC::ptr()),
+        /// Generic Timer Controller.
+        pub gtc: Gtc = Gtc::__new(GTC::ptr()),
+        /// Inter-Integrated Circuit 0.
+        pub i2c0: I2c<0> = I2c::__new(I2C0::ptr()),
+        /// Inter-Integrated Circuit 1.
+        pub i2c1: I2c<1> = I2c::__new(I2C1::ptr()),
+        /// Inter-Integrated Circuit 2.
+        pub i2c2: I2c<2> = I2c::__new(I2C2::ptr()),
+        /// Pulse Width Modulation.
+        pub pwm: PwmChannels = PwmChannels::__new(PWM::ptr()),
+        /// Core Local Interrupt.
+        pub clint: Clint = Clint::__new(CLINT::ptr()),
+        /// Core Local Interrupt Controller.
+        pub clic: Clic = Clic::__new(CLIC::ptr()),
+    }
+}
@@ -44,7 +44,7 @@ das Vergissmeinnicht, das du mir gegeben hast, ist hier"#;
 #[pbp_entry]
 fn pbp_main(boot_param: BootParam, _private_data: &[u8]) {
     check_startup(&boot_param);
-    let mut p = Peripherals::take();
+    let mut p = Peripherals::take().unwrap();
 
     let uart0_tx = p.gpioa.pa0.into_uart0_tx();
     let uart0_rx = p.gpioa.pa1.into_uart0_rx();
@@ -12,7 +12,7 @@ use panic_halt as _;
 #[pbp_entry]
 fn pbp_main(boot_param: BootParam, _private_data: &[u8]) {
     check_startup(&boot_param);
-    let mut p = Peripherals::take();
+    let mut p = Peripherals::take().unwrap();
     let mut delay = p.gtc.new_timer_delay(CntFreq::Freq4M, &mut p.cmu);
     let mut pa5 = p.gpioa.pa5.into_pull_up_output();
     let tx = p.gpioa.pa0.into_uart0_tx();
@@ -5,20 +5,22 @@ use artinchip_hal::gtc::CntFreq;
 use artinchip_hal::prelude::*;
 use artinchip_hal::uart::*;
 use artinchip_hal::wdog::RegWrMode;
+use artinchip_rt::core::crash::CrashRecord;
 use artinchip_rt::{Peripherals, pbp_entry, prelude::*};
 use log::info;
 use panic_halt as _;
 
 #[pbp_entry]
-fn pbp_main(boot_param: BootParam, _private_data: &[u8]) {
+fn pbp_main(boot_param: BootParam, private_data: &[u8]) {
     check_startup(&boot_param);
-    let mut p = Peripherals::take();
+    let mut p = Peripherals::take().unwrap();
     let tx = p.gpioa.pa0.into_uart0_tx();
     let rx = p.gpioa.pa1.into_uart0_rx();
 
     let _uart0 = uart_logger_init(p.uart0, tx, rx, UartConfig::default(), &mut p.cmu).unwrap();
     let mut delay = p.gtc.new_timer_delay(CntFreq::Freq4M, &mut p.cmu);
 
+    let crash = CrashRecord::take(p.rtc.register_block(), p.cmu.register_block());
     let reset_info = p.wri.new_reset_info();
     let time = p.rtc.new_real_time(&mut p.cmu);
     let mut wdog = p.wdog.new_driver(&mut p.cmu);
@@ -31,6 +33,14 @@ fn pbp_main(boot_param: BootParam, _private_data: &[u8]) {
     info!("Welcome to pbp boot info example by artinchip-hal🦀!");
 
     info!("Reset reason: {:?}", reset_info.reason());
+    if let Some(crash) = crash {
+        info!("Crash before reset: {:x?}", crash);
+    }
+    info!(
+        "Private data: {} bytes {:02x?}",
+        private_data.len(),
+        private_data
+    );
     info!("Watchdog active channel: {}", wdog.channel_id());
     info!("Watchdog write mode: {:?}", wdog.wr_mode());
     info!("Watchdog thresholds:");
@@ -19,7 +19,7 @@ static mut MEM_DST: MemBuf = MemBuf([0xDEAD_BEEFu32; 2000]);
 #[pbp_entry]
 fn pbp_main(boot_param: BootParam, _private_data: &[u8]) {
     check_startup(&boot_param);
-    let mut p = Peripherals::take();
+    let mut p = Peripherals::take().unwrap();
 
     let tx = p.gpioa.pa0.into_uart0_tx();
     let rx = p.gpioa.pa1.into_uart0_rx();
@@ -16,7 +16,7 @@ use w25qxxxjv::{Model, SpiSpeed, W25QXXXJV};
 #[pbp_entry]
 fn pbp_main(boot_param: BootParam, _private_data: &[u8]) {
     check_startup(&boot_param);
-    let mut p = Peripherals::take();
+    let mut p = Peripherals::take().unwrap();
 
     let tx = p.gpioa.pa0.into_uart0_tx();
     let rx = p.gpioa.pa1.into_uart0_rx();